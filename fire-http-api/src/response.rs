@@ -60,10 +60,9 @@ impl<'a, R> Extractor<'a, R> for &'a mut ResponseSettings {
 	type Prepared = ();
 
 	extractor_validate!(|validate| {
-		assert!(
-			validate.state.validate::<StateRefCell<ResponseSettings>>(),
-			"ResponseSettings not in state"
-		);
+		if !validate.state.validate::<StateRefCell<ResponseSettings>>() {
+			return Err("ResponseSettings not in state".into());
+		}
 	});
 
 	extractor_prepare!();
@@ -17,7 +17,7 @@ use tracing::{error, trace};
 
 use fire::header::{Method, RequestHeader};
 use fire::routes::{
-	HyperRequest, ParamsNames, PathParams, RawRoute, RoutePath,
+	HyperRequest, ParamsNames, PathParams, RawRoute, RouteKind, RoutePath,
 };
 pub use fire::util::PinnedFuture;
 use fire::ws::{self, JsonError, WebSocket};
@@ -88,6 +88,10 @@ impl StreamServer {
 }
 
 impl RawRoute for StreamServer {
+	fn kind(&self) -> RouteKind {
+		RouteKind::Stream
+	}
+
 	fn path(&self) -> RoutePath {
 		RoutePath {
 			method: Some(Method::GET),
@@ -121,10 +125,12 @@ impl RawRoute for StreamServer {
 			// we need to spawn a future because
 			// upgrade on can only be fufilled after
 			// we send SWITCHING_PROTOCOLS
-			tokio::task::spawn(async move {
+			ws::util::spawn(req, |signal| async move {
 				match on_upgrade.await {
 					Ok(upgraded) => {
-						let ws = WebSocket::new(upgraded).await;
+						let ws = WebSocket::new(upgraded)
+							.await
+							.with_shutdown(signal);
 
 						trace!("connection upgraded");
 
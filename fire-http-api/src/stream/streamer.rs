@@ -99,10 +99,9 @@ where
 	type Prepared = Self;
 
 	extractor_validate!(|validate| {
-		assert!(
-			validate.state.validate::<Self>(),
-			"Stream not found in state"
-		);
+		if !validate.state.validate::<Self>() {
+			return Err("Stream not found in state".into());
+		}
 		// remove it from the state since we will use it
 		validate.state.remove::<Self>();
 	});
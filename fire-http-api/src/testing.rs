@@ -77,7 +77,7 @@ impl FirePitApi {
 			Request::builder(uri.as_ref().parse().unwrap()).method(R::METHOD);
 		*raw_req.values_mut() = header;
 
-		let mut req = if R::METHOD == &Method::GET {
+		let mut req = if R::METHOD == Method::GET {
 			raw_req.serialize_query(req).unwrap().build()
 		} else {
 			raw_req
@@ -83,18 +83,20 @@ pub fn transform_body_to_response<R: Request>(
 	Ok(resp.build())
 }
 
-pub fn validate_request<R: Request>(ty: &str) {
+pub fn validate_request<R: Request>(ty: &str) -> Result<(), String> {
 	// if it is a get request, make sure the type cannot be parsed from a a null value
 	// since that would mean no request can go through
 	if R::METHOD != Method::GET {
-		return;
+		return Ok(());
 	}
 
 	let r = serde_json::from_value::<R>(serde_json::Value::Null);
 	if r.is_ok() {
-		panic!(
+		return Err(format!(
 			"Get request cannot be parsed from a null value, \
 		make sure the type {ty} is not a unit struct"
-		);
+		));
 	}
+
+	Ok(())
 }
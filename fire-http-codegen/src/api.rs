@@ -53,7 +53,7 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 
 				<#ty as #extractor_type>::validate(
 					validate
-				);
+				)?;
 			}));
 		}
 
@@ -63,6 +63,19 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 				params: &#fire::routes::ParamsNames,
 				resources: &#fire::resources::Resources
 			) {
+				let res = #fire::routes::Route::try_validate_requirements(
+					self, params, resources
+				);
+				if let Err(e) = res {
+					panic!("{e}");
+				}
+			}
+
+			fn try_validate_requirements(
+				&self,
+				params: &#fire::routes::ParamsNames,
+				resources: &#fire::resources::Resources
+			) -> std::result::Result<(), String> {
 				#[allow(unused_mut, dead_code)]
 				let mut state = #fire::state::StateValidation::new();
 				state.insert::<#fire::state::StateRefCell<
@@ -71,7 +84,7 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 
 				#(#asserts)*
 
-				#fire_api::util::validate_request::<#req_ty>(stringify!(#req_ty));
+				#fire_api::util::validate_request::<#req_ty>(stringify!(#req_ty))
 			}
 		)
 	};
@@ -84,6 +97,10 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 				path: #ty_as_req::PATH.into()
 			}
 		}
+
+		fn kind(&self) -> #fire::routes::RouteKind {
+			#fire::routes::RouteKind::Api
+		}
 	);
 
 	let handler_fn = {
@@ -61,7 +61,7 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 
 				<#ty as #extractor_type>::validate(
 					validate
-				);
+				)?;
 			}));
 		}
 
@@ -71,11 +71,18 @@ pub(crate) fn expand(args: ApiArgs, item: ItemFn) -> Result<TokenStream> {
 				params: &#fire::routes::ParamsNames,
 				resources: &#fire::resources::Resources
 			) {
-				#[allow(unused_mut, dead_code)]
-				let mut state = #fire::state::StateValidation::new();
-				state.insert::<#stream_ty>();
+				let res = (|| {
+					#[allow(unused_mut, dead_code)]
+					let mut state = #fire::state::StateValidation::new();
+					state.insert::<#stream_ty>();
 
-				#(#asserts)*
+					#(#asserts)*
+
+					Ok::<_, String>(())
+				})();
+				if let Err(e) = res {
+					panic!("{e}");
+				}
 			}
 		)
 	};
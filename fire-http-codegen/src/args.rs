@@ -1,5 +1,6 @@
 use syn::parse::{Parse, ParseStream, Result};
-use syn::LitStr;
+use syn::{Ident, LitInt, LitStr, Token};
+use template::{is_word, TemplatePart, TYPE_CONSTRAINTS};
 
 #[cfg(feature = "api")]
 pub(crate) use api::*;
@@ -7,14 +8,114 @@ pub(crate) use api::*;
 #[derive(Debug, Clone)]
 pub(crate) struct Args {
 	pub uri: String,
+	pub name: Option<String>,
+	/// The body size limit in bytes.
+	pub size_limit: Option<usize>,
+	/// The body timeout in seconds.
+	pub timeout: Option<u64>,
 }
 
 impl Parse for Args {
 	fn parse(input: ParseStream) -> Result<Self> {
 		// parse a string
 		let uri: LitStr = input.parse()?;
+		let mut name = None;
+		let mut size_limit = None;
+		let mut timeout = None;
 
-		Ok(Self { uri: uri.value() })
+		// followed by optional `key = value` pairs
+		while !input.is_empty() {
+			input.parse::<Token![,]>()?;
+			if input.is_empty() {
+				break;
+			}
+
+			let ident: Ident = input.parse()?;
+			input.parse::<Token![=]>()?;
+
+			if ident == "name" {
+				let value: LitStr = input.parse()?;
+				name = Some(value.value());
+			} else if ident == "size_limit" {
+				size_limit = Some(parse_non_zero::<usize>(input)?);
+			} else if ident == "timeout" {
+				timeout = Some(parse_non_zero::<u64>(input)?);
+			} else {
+				return Err(syn::Error::new(
+					ident.span(),
+					"expected `name`, `size_limit` or `timeout`",
+				));
+			}
+		}
+
+		validate_uri(&uri)?;
+
+		Ok(Self {
+			uri: uri.value(),
+			name,
+			size_limit,
+			timeout,
+		})
+	}
+}
+
+/// Returns an error if the uri is not a valid template or contains an
+/// unknown type constraint.
+fn validate_uri(uri: &LitStr) -> Result<()> {
+	let value = uri.value();
+	let parts = template::parse(&value).map_err(|e| {
+		syn::Error::new(uri.span(), format!("invalid uri: {e}"))
+	})?;
+
+	for part in parts {
+		let TemplatePart::Param {
+			constraint: Some(constraint),
+			..
+		} = part
+		else {
+			continue;
+		};
+
+		if is_word(constraint) && !TYPE_CONSTRAINTS.contains(&constraint) {
+			return Err(syn::Error::new(
+				uri.span(),
+				format!("unknown constraint {constraint:?}"),
+			));
+		}
+	}
+
+	Ok(())
+}
+
+/// Parses an integer literal which fits into `N` and is not zero.
+fn parse_non_zero<N>(input: ParseStream) -> Result<N>
+where
+	N: std::str::FromStr + PartialEq + Default,
+	N::Err: std::fmt::Display,
+{
+	let lit: LitInt = input.parse()?;
+	let num = lit.base10_parse::<N>()?;
+	if num == N::default() {
+		return Err(syn::Error::new(lit.span(), "cannot be zero"));
+	}
+
+	Ok(num)
+}
+
+/// The arguments of the `route` attribute, the method and then the uri.
+#[derive(Clone)]
+pub(crate) struct RouteArgs {
+	pub method: LitStr,
+	pub args: Args,
+}
+
+impl Parse for RouteArgs {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let method: LitStr = input.parse()?;
+		input.parse::<Token![,]>()?;
+		let args = input.parse()?;
+
+		Ok(Self { method, args })
 	}
 }
 
@@ -22,7 +123,7 @@ impl Parse for Args {
 mod api {
 	use super::*;
 
-	use syn::{Ident, LitBool, Token, Type};
+	use syn::{LitBool, Type};
 
 	#[derive(Clone)]
 	pub(crate) struct ApiArgs {
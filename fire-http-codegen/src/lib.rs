@@ -13,11 +13,11 @@ mod ws;
 
 #[cfg(feature = "api")]
 use args::ApiArgs;
-use args::Args;
+use args::{Args, RouteArgs};
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, ItemFn, LitStr};
 
 enum Method {
 	Get,
@@ -25,17 +25,51 @@ enum Method {
 	Put,
 	Delete,
 	Head,
+	Other(String),
 }
 
 impl Method {
-	pub fn as_str(&self) -> &'static str {
-		match self {
+	/// Parses any method, extension methods need to be valid tokens.
+	fn parse(lit: &LitStr) -> syn::Result<Self> {
+		let method = lit.value();
+		let is_tchar = |b: u8| {
+			b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+		};
+
+		match method.as_str() {
+			"GET" => Ok(Self::Get),
+			"POST" => Ok(Self::Post),
+			"PUT" => Ok(Self::Put),
+			"DELETE" => Ok(Self::Delete),
+			"HEAD" => Ok(Self::Head),
+			m if !m.is_empty() && m.bytes().all(is_tchar) => {
+				Ok(Self::Other(method))
+			}
+			_ => Err(syn::Error::new(lit.span(), "invalid http method")),
+		}
+	}
+
+	/// Returns an expression which creates the `Method`.
+	fn to_tokens(
+		&self,
+		fire: &proc_macro2::TokenStream,
+	) -> proc_macro2::TokenStream {
+		let name = match self {
 			Self::Get => "GET",
 			Self::Post => "POST",
 			Self::Put => "PUT",
 			Self::Delete => "DELETE",
 			Self::Head => "HEAD",
-		}
+			Self::Other(method) => {
+				return quote!(
+					#fire::header::Method::from_bytes(#method.as_bytes())
+						.unwrap()
+				)
+			}
+		};
+
+		let ident = format_ident!("{name}");
+		quote!(#fire::header::Method::#ident)
 	}
 }
 
@@ -84,6 +118,26 @@ attribute_route!(put, Put, No);
 attribute_route!(delete, Delete, No);
 attribute_route!(head, Head, No);
 
+/// A route for any method, including extension methods like `PROPFIND`.
+///
+/// `#[route("PROPFIND", "/dav/{*path}")]`
+#[proc_macro_attribute]
+pub fn route(attrs: TokenStream, item: TokenStream) -> TokenStream {
+	let args = parse_macro_input!(attrs as RouteArgs);
+	let item = parse_macro_input!(item as ItemFn);
+
+	let method = match Method::parse(&args.method) {
+		Ok(method) => method,
+		Err(e) => return to_compile_error(e),
+	};
+
+	let stream = route::expand(args.args, item, method, TransformOutput::No);
+
+	stream
+		.map(|stream| stream.into())
+		.unwrap_or_else(to_compile_error)
+}
+
 attribute_route!(get_json, Get, Json);
 attribute_route!(post_json, Post, Json);
 attribute_route!(put_json, Put, Json);
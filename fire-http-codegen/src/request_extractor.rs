@@ -24,7 +24,11 @@ pub fn impl_extractor(fire: &TokenStream, ty: &TokenStream) -> TokenStream {
 			type Error = std::convert::Infallible;
 			type Prepared = ();
 
-			fn validate(_validate: #fire::extractor::Validate<'_>) {}
+			fn validate(
+				_validate: #fire::extractor::Validate<'_>,
+			) -> std::result::Result<(), String> {
+				Ok(())
+			}
 
 			fn prepare(
 				_prepare: #fire::extractor::Prepare<'_>,
@@ -19,12 +19,17 @@ pub fn expand(input: &DeriveInput) -> Result<proc_macro::TokenStream> {
 			type Error = std::convert::Infallible;
 			type Prepared = ();
 
-			fn validate(validate: #fire::extractor::Validate<'_>) {
-				assert!(
-					validate.resources.exists::<#ty>(),
-					"Resource {} does not exist",
-					stringify!(#ty)
-				);
+			fn validate(
+				validate: #fire::extractor::Validate<'_>,
+			) -> std::result::Result<(), String> {
+				if !validate.resources.exists::<#ty>() {
+					return Err(format!(
+						"Resource {} does not exist",
+						stringify!(#ty)
+					));
+				}
+
+				Ok(())
 			}
 
 			fn prepare(
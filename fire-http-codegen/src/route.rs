@@ -4,7 +4,8 @@ use crate::{Method, TransformOutput};
 
 use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
-use syn::{ItemFn, Result};
+use syn::{Ident, ItemFn, Result};
+use template::TemplatePart;
 
 pub(crate) fn expand(
 	args: Args,
@@ -40,7 +41,7 @@ pub(crate) fn expand(
 
 				<#ty as #extractor_type>::validate(
 					validate
-				);
+				)?;
 			}));
 		}
 
@@ -50,22 +51,37 @@ pub(crate) fn expand(
 				params: &#fire::routes::ParamsNames,
 				resources: &#fire::resources::Resources
 			) {
+				let res = #fire::routes::Route::try_validate_requirements(
+					self, params, resources
+				);
+				if let Err(e) = res {
+					panic!("{e}");
+				}
+			}
+
+			fn try_validate_requirements(
+				&self,
+				params: &#fire::routes::ParamsNames,
+				resources: &#fire::resources::Resources
+			) -> std::result::Result<(), String> {
 				#[allow(unused_mut, dead_code)]
 				let mut state = #fire::state::StateValidation::new();
 
 				#(#asserts)*
+
+				Ok(())
 			}
 		)
 	};
 
 	let path_fn = {
 		let uri = &args.uri;
-		let method = format_ident!("{}", method.as_str());
+		let method = method.to_tokens(&fire);
 
 		quote!(
 			fn path(&self) -> #fire::routes::RoutePath {
 				#fire::routes::RoutePath {
-					method: Some(#fire::header::Method::#method),
+					method: Some(#method),
 					path: #uri.into()
 				}
 			}
@@ -178,14 +194,24 @@ pub(crate) fn expand(
 		)
 	};
 
+	let url_fn = generate_url_fn(&fire, &item, &args.uri);
+	let name_fn = generate_name_fn(&args);
+	let limits_fn = generate_limits_fn(&args);
+
 	Ok(quote!(
 		#struct_gen
 
+		#url_fn
+
 		impl #fire::routes::Route for #struct_name {
 			#valid_data_fn
 
 			#path_fn
 
+			#name_fn
+
+			#limits_fn
+
 			#call_fn
 		}
 	))
@@ -202,3 +228,93 @@ pub(crate) fn generate_struct(item: &ItemFn) -> TokenStream {
 		#vis struct #struct_name;
 	)
 }
+
+/// Generates `url` with an argument for every parameter of the uri,
+/// parameters with a type constraint take that type.
+pub(crate) fn generate_url_fn(
+	fire: &TokenStream,
+	item: &ItemFn,
+	uri: &str,
+) -> TokenStream {
+	let struct_name = &item.sig.ident;
+	let vis = &item.vis;
+
+	// the template was already validated with the arguments
+	let mut names = vec![];
+	let mut types = vec![];
+	for part in template::parse(uri).unwrap_or_default() {
+		let TemplatePart::Param {
+			name, constraint, ..
+		} = part
+		else {
+			continue;
+		};
+		if names.contains(&name) {
+			continue;
+		}
+
+		names.push(name);
+		types.push(match constraint {
+			Some(ty) if template::TYPE_CONSTRAINTS.contains(&ty) => {
+				let ty = format_ident!("{}", ty);
+				quote!(#ty)
+			}
+			_ => quote!(impl std::fmt::Display),
+		});
+	}
+
+	let idents: Vec<_> = names
+		.iter()
+		.enumerate()
+		.map(|(i, name)| {
+			syn::parse_str::<Ident>(name)
+				.unwrap_or_else(|_| format_ident!("param_{}", i))
+		})
+		.collect();
+
+	quote!(
+		impl #struct_name {
+			/// Returns the url of this route, see `RouteUrl`.
+			#[allow(dead_code, clippy::too_many_arguments)]
+			#vis fn url(
+				#(#idents: #types),*
+			) -> #fire::routes::RouteUrl {
+				#fire::routes::RouteUrl::from_template(
+					#uri,
+					&[#((#names, &#idents as &dyn std::fmt::Display)),*]
+				)
+			}
+		}
+	)
+}
+
+/// Generates `size_limit` and `timeout` if they were set.
+fn generate_limits_fn(args: &Args) -> TokenStream {
+	let size_limit = args.size_limit.map(|size_limit| {
+		quote!(
+			fn size_limit(&self) -> Option<usize> {
+				Some(#size_limit)
+			}
+		)
+	});
+	let timeout = args.timeout.map(|timeout| {
+		quote!(
+			fn timeout(&self) -> Option<std::time::Duration> {
+				Some(std::time::Duration::from_secs(#timeout))
+			}
+		)
+	});
+
+	quote!(#size_limit #timeout)
+}
+
+pub(crate) fn generate_name_fn(args: &Args) -> TokenStream {
+	match &args.name {
+		Some(name) => quote!(
+			fn name(&self) -> Option<&str> {
+				Some(#name)
+			}
+		),
+		None => quote!(),
+	}
+}
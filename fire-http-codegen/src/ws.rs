@@ -1,4 +1,4 @@
-use crate::route::generate_struct;
+use crate::route::{generate_name_fn, generate_struct, generate_url_fn};
 use crate::util::{fire_http_crate, validate_inputs, validate_signature};
 use crate::Args;
 
@@ -31,7 +31,7 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 
 				<#ty as #extractor_type>::validate(
 					validate
-				);
+				)?;
 			}));
 		}
 
@@ -41,10 +41,25 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 				params: &#fire::routes::ParamsNames,
 				resources: &#fire::resources::Resources
 			) {
+				let res = #fire::routes::RawRoute::try_validate_requirements(
+					self, params, resources
+				);
+				if let Err(e) = res {
+					panic!("{e}");
+				}
+			}
+
+			fn try_validate_requirements(
+				&self,
+				params: &#fire::routes::ParamsNames,
+				resources: &#fire::resources::Resources
+			) -> std::result::Result<(), String> {
 				#[allow(unused_mut, dead_code)]
 				let mut state = #fire::state::StateValidation::new();
 
 				#(#asserts)*
+
+				Ok(())
 			}
 		)
 	};
@@ -59,6 +74,10 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 					path: #uri.into()
 				}
 			}
+
+			fn kind(&self) -> #fire::routes::RouteKind {
+				#fire::routes::RouteKind::WebSocket
+			}
 		)
 	};
 
@@ -128,6 +147,17 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 			handler_args.push(quote!(#var_name));
 		}
 
+		let size_limit = match args.size_limit {
+			Some(size_limit) => quote!(Some(#size_limit)),
+			None => quote!(None),
+		};
+		let timeout = match args.timeout {
+			Some(timeout) => {
+				quote!(Some(std::time::Duration::from_secs(#timeout)))
+			}
+			None => quote!(None),
+		};
+
 		quote!(
 			fn call<'a>(
 				&'a self,
@@ -163,12 +193,12 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 						#(#prepare_extractors),*
 					);
 
-					#fire::ws::util::spawn(async move {
+					#fire::ws::util::spawn(req, |signal| async move {
 						match on_upgrade.await {
 							Ok(upgraded) => {
-								let ws = #fire::ws::WebSocket::new(
-									upgraded
-								).await;
+								let ws = #fire::ws::WebSocket::with_limits(
+									upgraded, #size_limit, #timeout
+								).await.with_shutdown(signal);
 								let mut ws = Some(ws);
 
 								#(#handler_args_vars)*
@@ -189,14 +219,21 @@ pub(crate) fn expand(args: Args, item: ItemFn) -> Result<TokenStream> {
 		)
 	};
 
+	let url_fn = generate_url_fn(&fire, &item, &args.uri);
+	let name_fn = generate_name_fn(&args);
+
 	Ok(quote!(
 		#struct_gen
 
+		#url_fn
+
 		impl #fire::routes::RawRoute for #struct_name {
 			#valid_data_fn
 
 			#path_fn
 
+			#name_fn
+
 			#call_fn
 		}
 	))
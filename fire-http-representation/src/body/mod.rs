@@ -32,7 +32,9 @@ type BoxedSyncRead = Box<dyn SyncRead + Send + Sync>;
 type PinnedAsyncBytesStream =
 	Pin<Box<dyn AsyncStream<Item = io::Result<Bytes>> + Send + Sync>>;
 
+#[derive(Default)]
 enum Inner {
+	#[default]
 	Empty,
 	// Bytes will never be empty
 	Bytes(Bytes),
@@ -55,12 +57,6 @@ impl fmt::Debug for Inner {
 	}
 }
 
-impl Default for Inner {
-	fn default() -> Self {
-		Self::Empty
-	}
-}
-
 #[derive(Debug, Clone, Default)]
 struct Constraints {
 	timeout: Option<Duration>,
@@ -296,7 +292,7 @@ fn timed_out(msg: &'static str) -> io::Error {
 }
 
 fn join_error(error: task::JoinError) -> io::Error {
-	io::Error::new(io::ErrorKind::Other, error)
+	io::Error::other(error)
 }
 
 #[cfg(test)]
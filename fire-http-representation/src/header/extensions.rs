@@ -0,0 +1,72 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Typed values attached to a request, for example by a middleware, which
+/// can then be read by handlers.
+///
+/// Values are shared when the header gets cloned.
+#[derive(Clone, Default)]
+pub struct Extensions {
+	inner: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Inserts a value replacing the previous value of the same type.
+	pub fn insert<T>(&mut self, value: T)
+	where
+		T: Any + Send + Sync,
+	{
+		self.inner.insert(TypeId::of::<T>(), Arc::new(value));
+	}
+
+	pub fn contains<T>(&self) -> bool
+	where
+		T: Any + Send + Sync,
+	{
+		self.inner.contains_key(&TypeId::of::<T>())
+	}
+
+	pub fn get<T>(&self) -> Option<&T>
+	where
+		T: Any + Send + Sync,
+	{
+		self.inner
+			.get(&TypeId::of::<T>())
+			.map(|v| v.downcast_ref::<T>().unwrap())
+	}
+
+	/// Removes the value and returns it.
+	///
+	/// The value is shared with clones of the extensions, which still
+	/// contain it, use `Arc::try_unwrap` to get the value back.
+	pub fn remove<T>(&mut self) -> Option<Arc<T>>
+	where
+		T: Any + Send + Sync,
+	{
+		self.inner
+			.remove(&TypeId::of::<T>())
+			.map(|v| v.downcast::<T>().unwrap())
+	}
+
+	pub fn len(&self) -> usize {
+		self.inner.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.inner.is_empty()
+	}
+}
+
+impl fmt::Debug for Extensions {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Extensions")
+			.field("len", &self.inner.len())
+			.finish()
+	}
+}
@@ -1,4 +1,6 @@
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 pub use http::{Method, StatusCode, Uri};
 
@@ -11,23 +13,115 @@ pub use contenttype::{ContentType, Mime};
 pub mod values;
 pub use values::{HeaderValue, HeaderValues};
 
+mod extensions;
+pub use extensions::Extensions;
+
 pub use constants::*;
 
+/// The address of the peer a request was received from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PeerAddr {
+	/// A peer connected over tcp.
+	Tcp(SocketAddr),
+	/// A peer connected over a unix domain socket.
+	///
+	/// The path is only set if the peer socket was bound to one, which is
+	/// mostly not the case.
+	Unix(Option<PathBuf>),
+}
+
+impl PeerAddr {
+	/// Returns the socket address if the peer is connected over tcp.
+	pub fn socket_addr(&self) -> Option<SocketAddr> {
+		match self {
+			Self::Tcp(addr) => Some(*addr),
+			Self::Unix(_) => None,
+		}
+	}
+
+	/// Returns the address which is used for `RequestHeader::address`.
+	///
+	/// Peers which are not connected over tcp are represented as
+	/// `0.0.0.0:0`.
+	pub fn to_socket_addr(&self) -> SocketAddr {
+		self.socket_addr()
+			.unwrap_or_else(|| (Ipv4Addr::UNSPECIFIED, 0).into())
+	}
+}
+
+impl From<SocketAddr> for PeerAddr {
+	fn from(addr: SocketAddr) -> Self {
+		Self::Tcp(addr)
+	}
+}
+
 /// RequestHeader received from a client.
+///
+/// Fields may be added in the future, use `RequestHeader::new` to create
+/// one.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct RequestHeader {
+	/// The ip address of the client.
+	///
+	/// If the request was forwarded by a trusted proxy this is the address
+	/// the proxy reported. If the client is not connected over tcp this is
+	/// `0.0.0.0:0`, see `peer` for the real address.
 	pub address: SocketAddr,
+	pub peer: PeerAddr,
+	/// The name of the listener the request was received on.
+	pub listener: Option<Arc<str>>,
 	pub method: Method,
 	pub uri: Uri,
 	pub values: HeaderValues,
+	/// Typed values attached to the request, for example by a middleware.
+	pub extensions: Extensions,
 }
 
 impl RequestHeader {
+	/// Creates a `RequestHeader` which was received from the address, with
+	/// no listener and no extensions.
+	pub fn new(
+		address: SocketAddr,
+		method: Method,
+		uri: Uri,
+		values: HeaderValues,
+	) -> Self {
+		Self {
+			address,
+			peer: address.into(),
+			listener: None,
+			method,
+			uri,
+			values,
+			extensions: Extensions::new(),
+		}
+	}
+
 	/// Returns the ip address of the requesting client.
+	///
+	/// If the client is not connected over tcp this returns `0.0.0.0:0`,
+	/// use `peer` to get the real address.
 	pub fn address(&self) -> &SocketAddr {
 		&self.address
 	}
 
+	/// Returns the address of the peer which might not be an ip address.
+	///
+	/// This is always the address of the connection, even if the request was
+	/// forwarded by a proxy.
+	pub fn peer(&self) -> &PeerAddr {
+		&self.peer
+	}
+
+	/// Returns the name of the listener the request was received on.
+	///
+	/// Is `None` if the request was not received by a listener, for example
+	/// if it was routed directly.
+	pub fn listener(&self) -> Option<&str> {
+		self.listener.as_deref()
+	}
+
 	/// Returns the requesting method.
 	pub fn method(&self) -> &Method {
 		&self.method
@@ -107,6 +107,14 @@ impl HeaderValues {
 		self.0.get_mut(key)
 	}
 
+	/// Removes the value returning it if it existed.
+	pub fn remove<K>(&mut self, key: K) -> Option<HeaderValue>
+	where
+		K: AsHeaderName,
+	{
+		self.0.remove(key)
+	}
+
 	/// Returns the value as a string if it exists and is valid.
 	pub fn get_str<K>(&self, key: K) -> Option<&str>
 	where
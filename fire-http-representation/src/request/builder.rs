@@ -2,7 +2,7 @@ use super::Request;
 use crate::body::Body;
 use crate::header::{
 	values::IntoHeaderName, ContentType, HeaderValue, HeaderValues, Method,
-	RequestHeader, Uri, CONTENT_LENGTH, CONTENT_TYPE,
+	PeerAddr, RequestHeader, Uri, CONTENT_LENGTH, CONTENT_TYPE,
 };
 
 use std::fmt;
@@ -20,20 +20,38 @@ pub struct RequestBuilder {
 impl RequestBuilder {
 	/// Creates a new `RequestBuilder`.
 	pub fn new(uri: Uri) -> Self {
+		let address: SocketAddr = ([127, 0, 0, 1], 0).into();
+
 		Self {
-			header: RequestHeader {
-				address: ([127, 0, 0, 1], 0).into(),
-				method: Method::GET,
+			header: RequestHeader::new(
+				address,
+				Method::GET,
 				uri,
-				values: HeaderValues::new(),
-			},
+				HeaderValues::new(),
+			),
 			body: Body::new(),
 		}
 	}
 
 	/// Sets the address.
 	pub fn address(mut self, addr: impl Into<SocketAddr>) -> Self {
-		self.header.address = addr.into();
+		let addr = addr.into();
+		self.header.address = addr;
+		self.header.peer = addr.into();
+		self
+	}
+
+	/// Sets the peer address, this also sets the address.
+	pub fn peer(mut self, peer: impl Into<PeerAddr>) -> Self {
+		let peer = peer.into();
+		self.header.address = peer.to_socket_addr();
+		self.header.peer = peer;
+		self
+	}
+
+	/// Sets the name of the listener.
+	pub fn listener(mut self, name: impl AsRef<str>) -> Self {
+		self.header.listener = Some(name.as_ref().into());
 		self
 	}
 
@@ -0,0 +1,191 @@
+//! Parses the path templates of fire http routes like
+//! `/users/{id:u64}/files/{*path}`.
+//!
+//! Used by fire-http and by the route macros, so both see the same
+//! parameters.
+
+use std::fmt;
+
+/// A part of a path template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplatePart<'a> {
+	/// Text which still contains the escapes `{{` and `}}`.
+	Text(&'a str),
+	Param {
+		name: &'a str,
+		catch_all: bool,
+		/// The part after the colon in `{id:u64}`.
+		constraint: Option<&'a str>,
+	},
+}
+
+/// The error returned if a template is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TemplateError {
+	UnexpectedEnd,
+	UnexpectedBrace,
+	Escape,
+}
+
+impl fmt::Display for TemplateError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::UnexpectedEnd => f.write_str("unexpected end of string"),
+			Self::UnexpectedBrace => f.write_str("unexpected {"),
+			Self::Escape => {
+				f.write_str("escapping does not work in template string")
+			}
+		}
+	}
+}
+
+impl std::error::Error for TemplateError {}
+
+// we need to parse {} and {name:constraint}
+// and allow it to be escaped with {{ or }}
+pub fn parse(s: &str) -> Result<Vec<TemplatePart<'_>>, TemplateError> {
+	let mut parts = vec![];
+	let mut rest = s;
+
+	loop {
+		let (text, param) = match rest.find('{') {
+			Some(pos) => (&rest[..pos], Some(&rest[pos + 1..])),
+			None => (rest, None),
+		};
+		if !text.is_empty() {
+			parts.push(TemplatePart::Text(text));
+		}
+
+		// either we're at the end or we found a {
+		let Some(param) = param else {
+			return Ok(parts);
+		};
+
+		// handle escapes
+		if let Some(r) = param.strip_prefix('{') {
+			parts.push(TemplatePart::Text("{{"));
+			rest = r;
+			continue;
+		}
+
+		let end = param
+			.find(['{', '}', ':'])
+			.ok_or(TemplateError::UnexpectedEnd)?;
+		let (s, constraint) = match param.as_bytes()[end] {
+			b'{' => return Err(TemplateError::UnexpectedBrace),
+			b'}' => {
+				if param[end + 1..].starts_with('}') {
+					return Err(TemplateError::Escape);
+				}
+				rest = &param[end + 1..];
+				(&param[..end], None)
+			}
+			_ => {
+				// the constraint can contain braces like [0-9]{3}
+				let constraint = &param[end + 1..];
+				let mut depth = 0usize;
+				let len = constraint
+					.find(|c| match c {
+						'{' => {
+							depth += 1;
+							false
+						}
+						'}' if depth == 0 => true,
+						'}' => {
+							depth -= 1;
+							false
+						}
+						_ => false,
+					})
+					.ok_or(TemplateError::UnexpectedEnd)?;
+				rest = &constraint[len + 1..];
+				(&param[..end], Some(&constraint[..len]))
+			}
+		};
+
+		let name = s.trim_start_matches('*');
+		parts.push(TemplatePart::Param {
+			name,
+			catch_all: name.len() != s.len(),
+			constraint,
+		});
+	}
+}
+
+/// The constraints which are types like `{id:u64}`, other constraints which
+/// are a single word are invalid and every other constraint is a regex.
+pub const TYPE_CONSTRAINTS: &[&str] = &[
+	"u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+	"i128", "isize", "f32", "f64", "bool",
+];
+
+/// Returns true if the constraint is a single word and therefore not a
+/// regex.
+pub fn is_word(constraint: &str) -> bool {
+	constraint
+		.bytes()
+		.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Returns the names of the parameters in the order they appear, every
+/// name only once.
+pub fn param_names(s: &str) -> Result<Vec<&str>, TemplateError> {
+	let mut names = vec![];
+
+	for part in parse(s)? {
+		if let TemplatePart::Param { name, .. } = part {
+			if !names.contains(&name) {
+				names.push(name);
+			}
+		}
+	}
+
+	Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parts() {
+		let parts = parse("/a{{b}}/{id:[0-9]{3}}/{*rest}").unwrap();
+		assert_eq!(
+			parts,
+			[
+				TemplatePart::Text("/a"),
+				TemplatePart::Text("{{"),
+				TemplatePart::Text("b}}/"),
+				TemplatePart::Param {
+					name: "id",
+					catch_all: false,
+					constraint: Some("[0-9]{3}"),
+				},
+				TemplatePart::Text("/"),
+				TemplatePart::Param {
+					name: "rest",
+					catch_all: true,
+					constraint: None,
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn names() {
+		assert_eq!(
+			param_names("/{{x}}/{a:u8}/{b}/{a}/{*c}").unwrap(),
+			["a", "b", "c"]
+		);
+		assert_eq!(param_names("/").unwrap(), Vec::<&str>::new());
+	}
+
+	#[test]
+	fn invalid() {
+		assert_eq!(parse("/{id"), Err(TemplateError::UnexpectedEnd));
+		assert_eq!(parse("/{a{b}"), Err(TemplateError::UnexpectedBrace));
+		assert_eq!(parse("/{a}}"), Err(TemplateError::Escape));
+		assert_eq!(parse("/{a:[0-9]{3}"), Err(TemplateError::UnexpectedEnd));
+	}
+}
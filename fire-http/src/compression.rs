@@ -0,0 +1,297 @@
+//! Response compression.
+//!
+//! ## Example
+//! ```
+//! # use fire_http as fire;
+//! use fire::compression::Compression;
+//!
+//! # async fn run() {
+//! let mut fire = fire::build("0.0.0.0:3000").await.unwrap();
+//! fire.add_middleware(Compression::new());
+//! # }
+//! ```
+
+use crate::header::{
+	ResponseHeader, StatusCode, ACCEPT_ENCODING, CACHE_CONTROL,
+	CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+};
+use crate::routes::Middleware;
+use crate::util::{add_vary, PinnedFuture};
+use crate::{Body, Request, Resources, Response};
+
+use async_compression::tokio::bufread::{
+	BrotliEncoder, DeflateEncoder, GzipEncoder, ZstdEncoder,
+};
+use async_compression::Level;
+use tokio::io::BufReader;
+
+/// A content encoding which can be used to compress responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+	Brotli,
+	Zstd,
+	Gzip,
+	Deflate,
+}
+
+impl Encoding {
+	/// Returns the name used in `Accept-Encoding` and `Content-Encoding`.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Brotli => "br",
+			Self::Zstd => "zstd",
+			Self::Gzip => "gzip",
+			Self::Deflate => "deflate",
+		}
+	}
+
+	fn matches(&self, name: &str) -> bool {
+		name.eq_ignore_ascii_case(self.as_str())
+			|| (*self == Self::Gzip && name.eq_ignore_ascii_case("x-gzip"))
+	}
+
+	fn encode(&self, body: Body) -> Body {
+		let reader = BufReader::new(body.into_async_reader());
+
+		match self {
+			// the default brotli level is too slow to compress on the fly
+			Self::Brotli => Body::from_async_reader(
+				BrotliEncoder::with_quality(reader, Level::Precise(4)),
+			),
+			Self::Zstd => Body::from_async_reader(ZstdEncoder::new(reader)),
+			Self::Gzip => Body::from_async_reader(GzipEncoder::new(reader)),
+			Self::Deflate => {
+				Body::from_async_reader(DeflateEncoder::new(reader))
+			}
+		}
+	}
+}
+
+/// A `Middleware` which compresses responses with the encoding the client
+/// prefers according to `Accept-Encoding`.
+///
+/// Bodies get compressed while they are sent, so streaming bodies are
+/// supported. Responses are not compressed if they are smaller than the
+/// minimum size, have no content type or one which is already compressed
+/// like images, already have a `Content-Encoding`, are partial or have
+/// `Cache-Control: no-transform`.
+///
+/// Compressed responses lose their `Content-Length` and a strong `ETag`
+/// becomes weak.
+#[derive(Debug, Clone)]
+pub struct Compression {
+	encodings: Vec<Encoding>,
+	min_size: usize,
+}
+
+impl Compression {
+	/// Creates a `Compression` which supports every encoding, preferring
+	/// brotli, then zstd, gzip and deflate, with a minimum size of 1024 bytes.
+	pub fn new() -> Self {
+		Self {
+			encodings: vec![
+				Encoding::Brotli,
+				Encoding::Zstd,
+				Encoding::Gzip,
+				Encoding::Deflate,
+			],
+			min_size: 1024,
+		}
+	}
+
+	/// Sets the supported encodings, in the order they are preferred if the
+	/// client accepts multiple with the same quality.
+	pub fn encodings(
+		mut self,
+		encodings: impl IntoIterator<Item = Encoding>,
+	) -> Self {
+		self.encodings = encodings.into_iter().collect();
+		self
+	}
+
+	/// Bodies with a known length smaller than this do not get compressed.
+	pub fn min_size(mut self, min_size: usize) -> Self {
+		self.min_size = min_size;
+		self
+	}
+
+	/// Returns the encoding with the highest quality the client accepts.
+	fn negotiate(&self, accept: &str) -> Option<Encoding> {
+		let accepted: Vec<_> = accept.split(',').filter_map(parse_q).collect();
+		let quality = |encoding: &Encoding| {
+			accepted
+				.iter()
+				.find(|(name, _)| encoding.matches(name))
+				.or_else(|| accepted.iter().find(|(name, _)| *name == "*"))
+				.map(|(_, q)| *q)
+				.unwrap_or(0.0)
+		};
+
+		let mut best: Option<(Encoding, f32)> = None;
+		for encoding in &self.encodings {
+			let q = quality(encoding);
+			if q > 0.0 && best.map_or(true, |(_, best)| q > best) {
+				best = Some((*encoding, q));
+			}
+		}
+
+		best.map(|(encoding, _)| encoding)
+	}
+
+	/// Returns true if the response could be compressed, independent of the
+	/// request.
+	fn compressible(&self, resp: &Response) -> bool {
+		let header = resp.header();
+		let values = header.values();
+
+		let skip_status = header.status_code.is_informational()
+			|| matches!(
+				header.status_code,
+				StatusCode::NO_CONTENT
+					| StatusCode::NOT_MODIFIED
+					| StatusCode::PARTIAL_CONTENT
+			);
+		let no_transform = values.get_str(CACHE_CONTROL).is_some_and(|c| {
+			c.split(',')
+				.any(|d| d.trim().eq_ignore_ascii_case("no-transform"))
+		});
+		if skip_status
+			|| no_transform
+			|| values.get(CONTENT_ENCODING).is_some()
+			|| values.get(CONTENT_RANGE).is_some()
+		{
+			return false;
+		}
+
+		if resp.body.len().is_some_and(|len| len < self.min_size) {
+			return false;
+		}
+
+		content_type(header).is_some_and(compressible_mime)
+	}
+}
+
+impl Default for Compression {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Middleware for Compression {
+	fn after<'a>(
+		&'a self,
+		req: &'a mut Request,
+		resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<()>> {
+		PinnedFuture::new(async move {
+			if !self.compressible(resp) {
+				return Ok(());
+			}
+
+			add_vary(&mut resp.header.values, ACCEPT_ENCODING.as_str());
+
+			let accept = req.header().value(ACCEPT_ENCODING);
+			let Some(encoding) = accept.and_then(|a| self.negotiate(a)) else {
+				return Ok(());
+			};
+
+			resp.body = encoding.encode(resp.body.take());
+			let values = &mut resp.header.values;
+			values.insert(CONTENT_ENCODING, encoding.as_str());
+			// the length is only known once everything was compressed
+			values.remove(CONTENT_LENGTH);
+			// the body is no longer the same byte for byte
+			let strong = values.get_str(ETAG).filter(|e| !e.starts_with("W/"));
+			if let Some(weak) = strong.map(|etag| format!("W/{etag}")) {
+				values.insert(ETAG, weak);
+			}
+
+			Ok(())
+		})
+	}
+}
+
+/// Parses an entry like `gzip;q=0.8`, ignoring invalid entries.
+fn parse_q(entry: &str) -> Option<(&str, f32)> {
+	let mut parts = entry.split(';').map(str::trim);
+	let name = parts.next().filter(|n| !n.is_empty())?;
+
+	let mut q = 1.0;
+	for param in parts {
+		if let Some(value) = param
+			.strip_prefix("q=")
+			.or_else(|| param.strip_prefix("Q="))
+		{
+			q = value.parse().ok().filter(|q| (0.0..=1.0).contains(q))?;
+		}
+	}
+
+	Some((name, q))
+}
+
+/// Returns the mime type without parameters.
+fn content_type(header: &ResponseHeader) -> Option<&str> {
+	let content_type = match header.content_type.as_str() {
+		"" => header.values().get_str(CONTENT_TYPE)?,
+		content_type => content_type,
+	};
+	let mime = content_type.split(';').next()?.trim();
+
+	(!mime.is_empty()).then_some(mime)
+}
+
+fn compressible_mime(mime: &str) -> bool {
+	let mime = mime.to_ascii_lowercase();
+	let (kind, sub) = mime.split_once('/').unwrap_or((&mime, ""));
+
+	match kind {
+		"image" => sub == "svg+xml",
+		"audio" | "video" => false,
+		"font" => !matches!(sub, "woff" | "woff2"),
+		"text" => sub != "event-stream",
+		_ => !matches!(
+			sub,
+			"zip"
+				| "gzip" | "x-gzip"
+				| "zstd" | "x-bzip2"
+				| "x-xz" | "x-7z-compressed"
+				| "x-rar-compressed"
+				| "octet-stream"
+				| "pdf"
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn negotiate() {
+		let compression = Compression::new();
+		let negotiate = |accept| compression.negotiate(accept);
+
+		assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+		assert_eq!(negotiate("gzip;q=1.0, br;q=0.5"), Some(Encoding::Gzip));
+		assert_eq!(negotiate("br;q=0, *"), Some(Encoding::Zstd));
+		assert_eq!(negotiate("identity"), None);
+		assert_eq!(negotiate("*;q=0"), None);
+		assert_eq!(negotiate("x-gzip"), Some(Encoding::Gzip));
+		assert_eq!(negotiate("gzip;q=2, deflate"), Some(Encoding::Deflate));
+
+		let gzip = Compression::new().encodings([Encoding::Gzip]);
+		assert_eq!(gzip.negotiate("br, gzip;q=0.1"), Some(Encoding::Gzip));
+	}
+
+	#[test]
+	fn mime_types() {
+		assert!(compressible_mime("text/html"));
+		assert!(compressible_mime("application/json"));
+		assert!(compressible_mime("image/svg+xml"));
+		assert!(!compressible_mime("image/png"));
+		assert!(!compressible_mime("application/zip"));
+		assert!(!compressible_mime("text/event-stream"));
+	}
+}
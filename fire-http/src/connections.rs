@@ -0,0 +1,205 @@
+//! Limits for the amount of open connections.
+
+use crate::header::PeerAddr;
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// What happens with a new connection if the maximum amount of connections
+/// is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+	/// The connection stays in the listen backlog of the os until another
+	/// connection closes.
+	#[default]
+	Wait,
+	/// The connection gets accepted and closed immediately.
+	Reject,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionLimits {
+	pub max: Option<usize>,
+	pub max_per_ip: Option<usize>,
+	pub overflow: Overflow,
+}
+
+impl ConnectionLimits {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// ## Panics
+	/// if is 0
+	pub fn max(&mut self, max: usize) {
+		assert!(max > 0, "max connections needs to be bigger than zero");
+		self.max = Some(max);
+	}
+
+	/// ## Panics
+	/// if is 0
+	pub fn max_per_ip(&mut self, max: usize) {
+		assert!(max > 0, "max connections needs to be bigger than zero");
+		self.max_per_ip = Some(max);
+	}
+
+	pub fn overflow(&mut self, overflow: Overflow) {
+		self.overflow = overflow;
+	}
+}
+
+/// The current usage of connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectionStats {
+	/// The amount of connections which are currently open.
+	pub current: usize,
+	/// The amount of different ip addresses which currently have an open
+	/// connection.
+	pub ips: usize,
+	/// The amount of connections which were closed because a limit was
+	/// reached.
+	pub rejected: u64,
+}
+
+/// Keeps track of every open connection, shared between all listeners.
+#[derive(Debug)]
+pub(crate) struct Connections {
+	limits: ConnectionLimits,
+	semaphore: Option<Arc<Semaphore>>,
+	current: AtomicUsize,
+	per_ip: Mutex<HashMap<IpAddr, usize>>,
+	rejected: AtomicU64,
+}
+
+impl Connections {
+	pub fn new(limits: ConnectionLimits) -> Self {
+		Self {
+			semaphore: limits.max.map(|max| Arc::new(Semaphore::new(max))),
+			limits,
+			current: AtomicUsize::new(0),
+			per_ip: Mutex::new(HashMap::new()),
+			rejected: AtomicU64::new(0),
+		}
+	}
+
+	/// Waits until a new connection is allowed to be accepted.
+	///
+	/// Returns immediately if connections over the limit should be rejected.
+	pub async fn ready(&self) -> Option<OwnedSemaphorePermit> {
+		match (&self.semaphore, self.limits.overflow) {
+			(Some(sem), Overflow::Wait) => {
+				// the semaphore never gets closed
+				Some(sem.clone().acquire_owned().await.unwrap())
+			}
+			_ => None,
+		}
+	}
+
+	/// Reserves a slot for a new accepted connection before its real source
+	/// is known, for example while waiting for a proxy protocol header.
+	///
+	/// Returns None if the maximum amount of connections is reached and the
+	/// connection should be closed.
+	pub fn reserve(
+		self: &Arc<Self>,
+		permit: Option<OwnedSemaphorePermit>,
+	) -> Option<ConnectionGuard> {
+		let permit = match (&self.semaphore, permit) {
+			(_, Some(permit)) => Some(permit),
+			(Some(sem), None) => match sem.clone().try_acquire_owned() {
+				Ok(permit) => Some(permit),
+				Err(_) => return self.reject(),
+			},
+			(None, None) => None,
+		};
+
+		self.current.fetch_add(1, Ordering::Relaxed);
+
+		Some(ConnectionGuard {
+			connections: self.clone(),
+			ip: None,
+			_permit: permit,
+		})
+	}
+
+	fn reject(&self) -> Option<ConnectionGuard> {
+		self.rejected.fetch_add(1, Ordering::Relaxed);
+		None
+	}
+
+	pub fn stats(&self) -> ConnectionStats {
+		ConnectionStats {
+			current: self.current.load(Ordering::Relaxed),
+			ips: self.per_ip.lock().unwrap().len(),
+			rejected: self.rejected.load(Ordering::Relaxed),
+		}
+	}
+
+	pub fn count_for_ip(&self, ip: IpAddr) -> usize {
+		self.per_ip.lock().unwrap().get(&ip).copied().unwrap_or(0)
+	}
+}
+
+/// Releases the connection once dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionGuard {
+	connections: Arc<Connections>,
+	ip: Option<IpAddr>,
+	_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ConnectionGuard {
+	/// Counts the connection for the ip address of the peer.
+	///
+	/// Returns None if the peer exceeds its limit and the connection should
+	/// be closed.
+	pub fn bind(mut self, peer: &PeerAddr) -> Option<Self> {
+		let Some(ip) = peer.socket_addr().map(|a| a.ip()) else {
+			return Some(self);
+		};
+
+		let connections = self.connections.clone();
+		let mut per_ip = connections.per_ip.lock().unwrap();
+		let count = per_ip.entry(ip).or_insert(0);
+
+		if connections
+			.limits
+			.max_per_ip
+			.is_some_and(|max| *count >= max)
+		{
+			// don't keep an empty entry around
+			if *count == 0 {
+				per_ip.remove(&ip);
+			}
+			drop(per_ip);
+
+			return connections.reject();
+		}
+
+		*count += 1;
+		self.ip = Some(ip);
+
+		Some(self)
+	}
+}
+
+impl Drop for ConnectionGuard {
+	fn drop(&mut self) {
+		self.connections.current.fetch_sub(1, Ordering::Relaxed);
+
+		let Some(ip) = self.ip else { return };
+
+		let mut per_ip = self.connections.per_ip.lock().unwrap();
+		if let Some(count) = per_ip.get_mut(&ip) {
+			*count -= 1;
+			if *count == 0 {
+				per_ip.remove(&ip);
+			}
+		}
+	}
+}
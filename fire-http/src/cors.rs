@@ -0,0 +1,303 @@
+//! Cross-Origin Resource Sharing.
+//!
+//! ## Example
+//! ```
+//! # use fire_http as fire;
+//! use fire::cors::Cors;
+//! use std::time::Duration;
+//!
+//! # async fn run() {
+//! let mut fire = fire::build("0.0.0.0:3000").await.unwrap();
+//! fire.add_middleware(
+//!     Cors::new()
+//!         .allow_origin("https://example.com")
+//!         .allow_headers(["content-type", "authorization"])
+//!         .allow_credentials()
+//!         .max_age(Duration::from_secs(600)),
+//! );
+//! # }
+//! ```
+
+use crate::fire::AllowedMethods;
+use crate::header::{
+	HeaderValues, Method, RequestHeader, StatusCode,
+	ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+	ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+	ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE,
+	ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+};
+use crate::routes::Middleware;
+use crate::util::{add_vary, PinnedFuture};
+use crate::{Request, Resources, Response};
+
+use std::time::Duration;
+
+/// A `Middleware` which adds CORS headers to responses and answers
+/// preflight requests.
+///
+/// Preflights are answered in `before`, so neither the route nor the
+/// middleware added afterwards see them. The allowed methods are the ones
+/// for which a route exists, restricted by `allow_methods`.
+///
+/// Preflights for paths without a route, even if the fallback answers them,
+/// or with a method, header or origin which is not allowed are passed on
+/// and their response gets no CORS headers, which makes the browser reject
+/// the request.
+///
+/// By default every origin is allowed, without credentials and without
+/// headers which are not safelisted.
+///
+/// Add it before any middleware which could reject preflights, like
+/// authentication, since preflights do not contain credentials.
+#[derive(Debug, Clone, Default)]
+pub struct Cors {
+	origins: Option<Vec<String>>,
+	methods: Option<Vec<Method>>,
+	headers: AllowHeaders,
+	expose_headers: Vec<String>,
+	credentials: bool,
+	max_age: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default)]
+enum AllowHeaders {
+	#[default]
+	None,
+	List(Vec<String>),
+	Any,
+}
+
+impl Cors {
+	/// Creates a `Cors` which allows every origin.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Allows requests from the origin, like `https://example.com`.
+	///
+	/// Once an origin was added only the listed origins are allowed.
+	pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+		let origin = origin.into().trim_end_matches('/').to_ascii_lowercase();
+		self.origins.get_or_insert_with(Vec::new).push(origin);
+		self
+	}
+
+	/// Restricts the methods which are allowed, by default every method for
+	/// which a route exists is allowed.
+	pub fn allow_methods(
+		mut self,
+		methods: impl IntoIterator<Item = Method>,
+	) -> Self {
+		self.methods.get_or_insert_with(Vec::new).extend(methods);
+		self
+	}
+
+	/// Allows requests to send the headers.
+	pub fn allow_headers<I>(mut self, headers: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Into<String>,
+	{
+		let headers =
+			headers.into_iter().map(|h| h.into().to_ascii_lowercase());
+		match &mut self.headers {
+			AllowHeaders::List(list) => list.extend(headers),
+			AllowHeaders::Any => {}
+			allow => *allow = AllowHeaders::List(headers.collect()),
+		}
+		self
+	}
+
+	/// Allows requests to send any header.
+	pub fn allow_any_header(mut self) -> Self {
+		self.headers = AllowHeaders::Any;
+		self
+	}
+
+	/// Allows the browser to read the response headers.
+	pub fn expose_headers<I>(mut self, headers: I) -> Self
+	where
+		I: IntoIterator,
+		I::Item: Into<String>,
+	{
+		self.expose_headers
+			.extend(headers.into_iter().map(Into::into));
+		self
+	}
+
+	/// Allows requests with credentials like cookies.
+	///
+	/// The origin of the request is returned instead of `*`.
+	pub fn allow_credentials(mut self) -> Self {
+		self.credentials = true;
+		self
+	}
+
+	/// Sets how long the browser may cache the result of a preflight.
+	pub fn max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = Some(max_age);
+		self
+	}
+
+	fn origin_allowed(&self, origin: &str) -> bool {
+		match &self.origins {
+			Some(origins) => {
+				let origin = origin.to_ascii_lowercase();
+				origins.contains(&origin)
+			}
+			None => true,
+		}
+	}
+
+	fn headers_allowed(&self, requested: &str) -> bool {
+		let mut requested = list(requested);
+		match &self.headers {
+			AllowHeaders::Any => true,
+			AllowHeaders::List(allowed) => requested
+				.all(|h| allowed.iter().any(|a| a.eq_ignore_ascii_case(h))),
+			AllowHeaders::None => requested.next().is_none(),
+		}
+	}
+
+	/// Returns the allowed methods out of the methods a route exists for.
+	fn allowed_methods<'a>(&self, routes: &'a [Method]) -> Vec<&'a str> {
+		routes
+			.iter()
+			.filter(|m| match &self.methods {
+				Some(methods) => methods.contains(m),
+				None => true,
+			})
+			.map(Method::as_str)
+			.collect()
+	}
+
+	/// Adds the headers every response to an allowed origin gets.
+	fn add_origin(&self, origin: &str, values: &mut HeaderValues) {
+		if self.origins.is_none() && !self.credentials {
+			values.insert(ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+		} else {
+			values.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+		}
+
+		if self.credentials {
+			values.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+		}
+	}
+
+	/// Returns the preflight response or `None` if the preflight is not
+	/// allowed.
+	fn preflight(&self, req: &RequestHeader, origin: &str) -> Option<Response> {
+		let requested = req.value(ACCESS_CONTROL_REQUEST_METHOD)?;
+		// no route exists for the path
+		let routes = &req.extensions.get::<AllowedMethods>()?.0;
+		let methods = self.allowed_methods(routes);
+		if !methods.contains(&requested) {
+			return None;
+		}
+
+		let req_headers = req.value(ACCESS_CONTROL_REQUEST_HEADERS);
+		if !self.headers_allowed(req_headers.unwrap_or("")) {
+			return None;
+		}
+
+		let mut resp = Response::builder()
+			.status_code(StatusCode::NO_CONTENT)
+			.build();
+		let values = &mut resp.header.values;
+		self.add_origin(origin, values);
+		values.insert(ACCESS_CONTROL_ALLOW_METHODS, methods.join(", "));
+		if let Some(req_headers) = req_headers.filter(|h| !h.is_empty()) {
+			let headers = match &self.headers {
+				AllowHeaders::Any => req_headers.to_string(),
+				_ => list(req_headers).collect::<Vec<_>>().join(", "),
+			};
+			values.insert(ACCESS_CONTROL_ALLOW_HEADERS, headers);
+		}
+		if let Some(max_age) = self.max_age {
+			values.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs());
+		}
+
+		Some(resp)
+	}
+}
+
+impl Middleware for Cors {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			let header = req.header();
+			if !is_preflight(header) {
+				return Ok(None);
+			}
+
+			let origin = header
+				.value(ORIGIN)
+				.filter(|origin| self.origin_allowed(origin));
+
+			Ok(origin.and_then(|origin| self.preflight(header, origin)))
+		})
+	}
+
+	fn after<'a>(
+		&'a self,
+		req: &'a mut Request,
+		resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<()>> {
+		PinnedFuture::new(async move {
+			let header = req.header();
+			let is_preflight = is_preflight(header);
+
+			let origin = header
+				.value(ORIGIN)
+				.filter(|origin| self.origin_allowed(origin));
+
+			match origin {
+				// answered in before if allowed
+				Some(_) if is_preflight => {}
+				Some(origin) => {
+					let values = &mut resp.header.values;
+					self.add_origin(origin, values);
+					if !self.expose_headers.is_empty() {
+						values.insert(
+							ACCESS_CONTROL_EXPOSE_HEADERS,
+							self.expose_headers.join(", "),
+						);
+					}
+				}
+				None => {}
+			}
+
+			// the response depends on the origin and for preflights on the
+			// requested method and headers
+			if self.origins.is_some() || self.credentials || is_preflight {
+				add_vary(&mut resp.header.values, ORIGIN.as_str());
+			}
+			if is_preflight {
+				add_vary(
+					&mut resp.header.values,
+					ACCESS_CONTROL_REQUEST_METHOD.as_str(),
+				);
+				add_vary(
+					&mut resp.header.values,
+					ACCESS_CONTROL_REQUEST_HEADERS.as_str(),
+				);
+			}
+
+			Ok(())
+		})
+	}
+}
+
+fn is_preflight(header: &RequestHeader) -> bool {
+	header.method == Method::OPTIONS
+		&& header.value(ACCESS_CONTROL_REQUEST_METHOD).is_some()
+}
+
+/// Splits a comma separated header value.
+fn list(value: &str) -> impl Iterator<Item = &str> {
+	value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
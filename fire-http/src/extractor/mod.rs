@@ -1,8 +1,9 @@
+use std::any::type_name;
 use std::convert::Infallible;
 use std::error::Error as StdError;
 use std::fmt;
 use std::pin::Pin;
-use std::str::FromStr;
+use std::str::{FromStr, Utf8Error};
 use std::{future::Future, ops::Deref};
 
 use types::header::RequestHeader;
@@ -46,7 +47,9 @@ pub trait Extractor<'a, R> {
 	type Error: ExtractorError;
 	type Prepared;
 
-	fn validate(validate: Validate<'_>);
+	/// Returns an error if a requirement is not met, for example if a
+	/// resource or a path parameter does not exist.
+	fn validate(validate: Validate<'_>) -> Result<(), String>;
 
 	#[allow(clippy::type_complexity)]
 	fn prepare(
@@ -179,7 +182,12 @@ where
 	type Prepared = ();
 
 	extractor_validate!(|validate| {
-		validate.resources.get::<T>().unwrap();
+		if !validate.resources.exists::<T>() {
+			return Err(format!(
+				"Resource {} does not exist",
+				type_name::<T>()
+			));
+		}
 	});
 
 	extractor_prepare!();
@@ -197,6 +205,63 @@ impl<T> Deref for Res<'_, T> {
 	}
 }
 
+/// A typed value from the extensions of the request header, which was for
+/// example inserted by a middleware.
+///
+/// Returns an internal server error if the value is missing.
+pub struct Ext<'a, T: ?Sized>(&'a T);
+
+impl<'a, T, R> Extractor<'a, R> for Ext<'a, T>
+where
+	T: Send + Sync + 'static,
+{
+	type Error = InternalError<ExtensionMissing>;
+	type Prepared = ();
+
+	extractor_validate!();
+
+	extractor_prepare!(|prepare| {
+		if !prepare.state.contains::<RequestHeader>() {
+			prepare
+				.state
+				.insert::<RequestHeader>(prepare.header.clone());
+		}
+
+		Ok(())
+	});
+
+	extractor_extract!(|extract| {
+		extract
+			.state
+			.get::<RequestHeader>()
+			.unwrap()
+			.extensions
+			.get::<T>()
+			.map(Ext)
+			.ok_or(InternalError(ExtensionMissing(type_name::<T>())))
+	});
+}
+
+impl<T> Deref for Ext<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		self.0
+	}
+}
+
+/// The error of `Ext`, contains the name of the missing type.
+#[derive(Debug)]
+pub struct ExtensionMissing(pub &'static str);
+
+impl fmt::Display for ExtensionMissing {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "request extension {} missing", self.0)
+	}
+}
+
+impl StdError for ExtensionMissing {}
+
 impl<'a> Extractor<'a, &'a mut Request> for &'a mut Request {
 	type Error = Infallible;
 	type Prepared = ();
@@ -249,11 +314,12 @@ where
 	type Prepared = ();
 
 	extractor_validate!(|validate| {
-		assert!(
-			validate.params.exists(validate.name),
-			"Path parameter `{}` does not exist",
-			validate.name
-		);
+		if !validate.params.exists(validate.name) {
+			return Err(format!(
+				"Path parameter `{}` does not exist",
+				validate.name
+			));
+		}
 	});
 
 	extractor_prepare!();
@@ -272,11 +338,12 @@ impl<'a, R> Extractor<'a, R> for &'a PathParam<str> {
 	type Prepared = ();
 
 	extractor_validate!(|validate| {
-		assert!(
-			validate.params.exists(validate.name),
-			"Path parameter `{}` does not exist",
-			validate.name
-		);
+		if !validate.params.exists(validate.name) {
+			return Err(format!(
+				"Path parameter `{}` does not exist",
+				validate.name
+			));
+		}
 	});
 
 	extractor_prepare!();
@@ -346,6 +413,99 @@ where
 	}
 }
 
+/// A path parameter which gets percent decoded before it is parsed, so
+/// `/files/a%20b` returns `a b`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDecoded<T>(T);
+
+impl<'a, T, R> Extractor<'a, R> for PathDecoded<T>
+where
+	T: Send + Sync + FromStr + 'a,
+	T::Err: StdError + Send + Sync + 'static,
+{
+	type Error = PathError<DecodeError<T::Err>>;
+	type Prepared = ();
+
+	extractor_validate!(|validate| {
+		if !validate.params.exists(validate.name) {
+			return Err(format!(
+				"Path parameter `{}` does not exist",
+				validate.name
+			));
+		}
+	});
+
+	extractor_prepare!();
+
+	extractor_extract!(|extract| {
+		let value = extract
+			.params
+			.get_decoded(extract.name)
+			.unwrap()
+			.map_err(|e| PathError(DecodeError::Utf8(e)))?;
+
+		value
+			.parse()
+			.map(PathDecoded)
+			.map_err(|e| PathError(DecodeError::Parse(e)))
+	});
+}
+
+impl<T> PathDecoded<T> {
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> Deref for PathDecoded<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<T> fmt::Display for PathDecoded<T>
+where
+	T: fmt::Display,
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+/// The error of `PathDecoded`.
+#[derive(Debug)]
+pub enum DecodeError<T> {
+	/// The decoded value is not valid utf8.
+	Utf8(Utf8Error),
+	Parse(T),
+}
+
+impl<T> fmt::Display for DecodeError<T>
+where
+	T: fmt::Display,
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Utf8(e) => write!(f, "invalid utf8: {e}"),
+			Self::Parse(e) => e.fmt(f),
+		}
+	}
+}
+
+impl<T> StdError for DecodeError<T>
+where
+	T: StdError + 'static,
+{
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		match self {
+			Self::Utf8(e) => Some(e),
+			Self::Parse(e) => Some(e),
+		}
+	}
+}
+
 impl<'a, R: 'a> Extractor<'a, R> for &'a Resources {
 	type Error = Infallible;
 	type Prepared = ();
@@ -1,30 +1,35 @@
-use crate::routes::Routes;
+use crate::connections::Connections;
+use crate::forwarded::TrustedProxies;
+use crate::routes::{Middleware, PathParams, Routes, TrailingSlash};
 use crate::server::HyperBody;
 use crate::util::{
-	convert_fire_resp_to_hyper_resp, convert_hyper_req_to_fire_req,
+	convert_fire_resp_to_hyper_resp, convert_hyper_req_to_fire_header,
+	convert_hyper_req_to_fire_req, request_host,
 };
-use crate::{Error, Request, Resources};
+use crate::{Body, Error, Request, Resources};
 
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use hyper::body::Incoming;
 use tracing::{error, info, info_span, warn, Instrument};
 
 use types::body::BodyHttp;
-use types::header::StatusCode;
+use types::header::{Method, StatusCode, Uri, ALLOW, CONTENT_LENGTH, LOCATION};
 use types::response::Response;
 
 const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
 // same as page size
 const DEFAULT_REQUEST_SIZE_LIMIT: usize = 4096; // 4kb
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct RequestConfigs {
 	pub timeout: Duration,
 	// in bytes
 	pub size_limit: usize,
+	pub trailing_slash: TrailingSlash,
 }
 
 impl RequestConfigs {
@@ -32,6 +37,7 @@ impl RequestConfigs {
 		Self {
 			timeout: DEFAULT_REQUEST_TIMEOUT,
 			size_limit: DEFAULT_REQUEST_SIZE_LIMIT,
+			trailing_slash: TrailingSlash::Strict,
 		}
 	}
 
@@ -45,6 +51,10 @@ impl RequestConfigs {
 		assert!(size_limit > 0, "size limit needs to be bigger than zero");
 		self.size_limit = size_limit;
 	}
+
+	pub fn trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+		self.trailing_slash = trailing_slash;
+	}
 }
 
 // IncredientsForAFire
@@ -80,14 +90,54 @@ impl Wood {
 	}
 }
 
+/// Holds the current `Wood` which can be swapped while the server is
+/// running and everything which stays the same between swaps.
+pub(crate) struct Hearth {
+	wood: RwLock<Arc<Wood>>,
+	connections: Arc<Connections>,
+	trusted_proxies: Option<TrustedProxies>,
+}
+
+impl Hearth {
+	pub fn new(
+		wood: Wood,
+		connections: Connections,
+		trusted_proxies: Option<TrustedProxies>,
+	) -> Self {
+		Self {
+			wood: RwLock::new(Arc::new(wood)),
+			connections: Arc::new(connections),
+			trusted_proxies,
+		}
+	}
+
+	/// Returns the current wood.
+	///
+	/// Requests should hold on to the returned wood until they are done.
+	pub fn wood(&self) -> Arc<Wood> {
+		self.wood.read().unwrap().clone()
+	}
+
+	/// Replaces the current wood, returning the previous one.
+	pub fn swap(&self, wood: Wood) -> Arc<Wood> {
+		std::mem::replace(&mut *self.wood.write().unwrap(), Arc::new(wood))
+	}
+
+	pub fn connections(&self) -> &Arc<Connections> {
+		&self.connections
+	}
+
+	pub fn trusted_proxies(&self) -> Option<&TrustedProxies> {
+		self.trusted_proxies.as_ref()
+	}
+}
+
 #[cfg(feature = "sentry")]
 pub(crate) async fn route_hyper(
 	wood: &Wood,
 	hyper_req: hyper::Request<Incoming>,
 	address: SocketAddr,
 ) -> Result<hyper::Response<BodyHttp>, Infallible> {
-	use std::sync::Arc;
-
 	use sentry_core::{Hub, Scope, SentryFutureExt};
 
 	let hub = Hub::new(Hub::current().client(), Arc::new(Scope::default()));
@@ -141,74 +191,155 @@ async fn route_hyper_inner(
 		info!(?status_code, "{method} {uri} | {status_code}");
 	}
 
-	let hyper_resp = convert_fire_resp_to_hyper_resp(resp);
+	let mut hyper_resp = convert_fire_resp_to_hyper_resp(resp);
+
+	if method == Method::HEAD {
+		strip_body(&mut hyper_resp);
+	}
 
 	Ok(hyper_resp)
 }
 
+/// Removes the body of a response to a head request, keeping the
+/// `Content-Length` of the body.
+fn strip_body(resp: &mut hyper::Response<BodyHttp>) {
+	let body = std::mem::replace(resp.body_mut(), Body::new().into_http_body());
+
+	if let Some(len) = hyper::body::Body::size_hint(&body).exact() {
+		resp.headers_mut()
+			.entry(CONTENT_LENGTH)
+			.or_insert(len.into());
+	}
+}
+
 async fn route_hyper_req(
 	wood: &Wood,
 	hyper_req: hyper::Request<Incoming>,
 	address: SocketAddr,
 ) -> Response {
 	let mut hyper_req = hyper_req.map(HyperBody::from);
+	let authority = request_host(&hyper_req);
+	let host = authority.as_ref().map(|a| a.host());
+	let mut path = hyper_req.uri().path().to_string();
+
+	let trailing_slash = wood.configs().trailing_slash;
+	if trailing_slash != TrailingSlash::Strict {
+		if let Some(alt) = wood.routes().trailing_slash_alternative(host, &path)
+		{
+			let uri = hyper_req.uri();
+			let path_and_query = match uri.query() {
+				Some(query) => format!("{alt}?{query}"),
+				None => alt.clone(),
+			};
+
+			if let Some(status_code) = trailing_slash.redirect() {
+				return Response::builder()
+					.status_code(status_code)
+					.header(LOCATION, path_and_query)
+					.build();
+			}
 
-	// route raw_routes
-	// response is Option<Response>
-	let resp = if let Some((route, params)) = wood
-		.routes()
-		.route_raw(hyper_req.method(), hyper_req.uri().path())
+			let mut parts = uri.clone().into_parts();
+			parts.path_and_query = path_and_query.parse().ok();
+			if let Ok(uri) = Uri::from_parts(parts) {
+				*hyper_req.uri_mut() = uri;
+				path = alt;
+			}
+		}
+	}
+
+	let middleware = wood.routes().middleware_at(host, &path);
+	// the number of middleware whose before was already called
+	let mut called = None;
+
+	// raw routes get the request without its body, the extensions set by
+	// middleware are passed with the hyper request
+	if let Some((route, params)) =
+		wood.routes().route_raw(hyper_req.method(), host, &path)
 	{
-		let res = route
-			.call(&mut hyper_req, address, &params, wood.data())
-			.await;
-		match res {
-			Some(Ok(res)) => Some(res),
-			Some(Err(e)) => {
-				error!("raw_route error: {}", e);
-				Some(e.status_code().into())
+		let header = match convert_hyper_req_to_fire_header(&hyper_req, address)
+		{
+			Ok(header) => header,
+			Err(e) => {
+				error!("Could not parse the hyper request: {e}");
+				return StatusCode::BAD_REQUEST.into();
+			}
+		};
+		let mut req = Request::new(header, Body::new());
+
+		let (before, early) = call_before(wood, &middleware, &mut req).await;
+		let resp = match early {
+			Some(resp) => Some(resp),
+			None => {
+				hyper_req
+					.extensions_mut()
+					.insert(req.header.extensions.clone());
+
+				let res = route
+					.call(&mut hyper_req, address, &params, wood.data())
+					.await;
+				match res {
+					Some(Ok(res)) => Some(res),
+					Some(Err(e)) => {
+						error!("raw_route error: {}", e);
+						Some(e.status_code().into())
+					}
+					None => None,
+				}
 			}
-			None => None,
+		};
+
+		if let Some(resp) = resp {
+			let mut resp = catch(wood, host, &path, &mut req, resp).await;
+			call_after(wood, &middleware[..before], &mut req, &mut resp).await;
+			return resp;
 		}
-	} else {
-		None
-	};
 
-	let req = convert_hyper_req_to_fire_req(hyper_req, address, wood.configs());
+		called = Some(before);
+	}
+
+	// the limits of the route apply before middleware can read the body
+	let req = convert_hyper_req_to_fire_req(hyper_req, address, |header| {
+		wood.routes().request_configs(header, wood.configs())
+	});
 	let mut req = match req {
 		Ok(r) => r,
 		Err(e) => {
-			if let Some(resp) = resp {
-				return resp;
-			}
 			error!("Could not parse the hyper request: {e}");
 			return StatusCode::BAD_REQUEST.into();
 		}
 	};
 
 	// normal route
-	let mut resp = if let Some(r) = resp {
-		r
-	} else {
-		match route(wood, &mut req).await {
-			Some(Ok(resp)) => resp,
-			Some(Err(error)) => {
-				error!(?error, "route error");
-				error.status_code().into()
-			}
-			None => StatusCode::NOT_FOUND.into(),
-		}
+	let (called, early) = match called {
+		Some(called) => (called, None),
+		None => call_before(wood, &middleware, &mut req).await,
 	};
+	let resp = match early {
+		Some(resp) => resp,
+		None => route_or_fallback(wood, &mut req).await,
+	};
+	// middleware like compression needs to see the final response
+	let mut resp = catch(wood, host, &path, &mut req, resp).await;
+	call_after(wood, &middleware[..called], &mut req, &mut resp).await;
 
-	// APPLY OVERRIDES
+	resp
+}
 
-	// check with catcher
-	for catcher in wood.routes().catchers() {
+/// Calls the catchers whose check passes.
+async fn catch(
+	wood: &Wood,
+	host: Option<&str>,
+	path: &str,
+	req: &mut Request,
+	mut resp: Response,
+) -> Response {
+	for catcher in wood.routes().catchers_at(host, path) {
 		if !catcher.check(req.header(), resp.header()) {
 			continue;
 		}
 
-		if let Err(e) = catcher.call(&mut req, &mut resp, wood.data()).await {
+		if let Err(e) = catcher.call(req, &mut resp, wood.data()).await {
 			resp = e.status_code().into();
 		}
 	}
@@ -216,14 +347,129 @@ async fn route_hyper_req(
 	resp
 }
 
+/// Calls the `before` of every middleware until one returns a response.
+///
+/// Returns how many were called and the response.
+async fn call_before(
+	wood: &Wood,
+	middleware: &[&dyn Middleware],
+	req: &mut Request,
+) -> (usize, Option<Response>) {
+	// used by cors to answer preflights
+	if req.header().method == Method::OPTIONS {
+		let uri = req.header().uri();
+		let allowed = wood.routes().allowed_methods(uri.host(), uri.path());
+		req.header.extensions.insert(AllowedMethods(allowed));
+	}
+
+	for (i, m) in middleware.iter().enumerate() {
+		match m.before(req, wood.data()).await {
+			Ok(None) => {}
+			Ok(Some(resp)) => return (i + 1, Some(resp)),
+			Err(error) => {
+				error!(?error, "middleware error");
+				return (i + 1, Some(error.status_code().into()));
+			}
+		}
+	}
+
+	(middleware.len(), None)
+}
+
+/// Calls the `after` of every middleware in the reverse order.
+async fn call_after(
+	wood: &Wood,
+	middleware: &[&dyn Middleware],
+	req: &mut Request,
+	resp: &mut Response,
+) {
+	for m in middleware.iter().rev() {
+		if let Err(error) = m.after(req, resp, wood.data()).await {
+			error!(?error, "middleware error");
+			*resp = error.status_code().into();
+		}
+	}
+}
+
+async fn route_or_fallback(wood: &Wood, req: &mut Request) -> Response {
+	let res = match route(wood, req).await {
+		Some(res) => Some(res),
+		None => fallback(wood, req).await,
+	};
+
+	match res {
+		Some(Ok(resp)) => resp,
+		Some(Err(error)) => {
+			error!(?error, "route error");
+			error.status_code().into()
+		}
+		None => not_found(wood, req),
+	}
+}
+
+/// Calls the fallback route if no route exists for the path, not even for
+/// another method.
+async fn fallback(
+	wood: &Wood,
+	req: &mut Request,
+) -> Option<Result<Response, Error>> {
+	let fallback = wood.routes().fallback()?;
+
+	let uri = req.header().uri();
+	if !wood
+		.routes()
+		.allowed_methods(uri.host(), uri.path())
+		.is_empty()
+	{
+		return None;
+	}
+
+	let params = PathParams::empty();
+	Some(fallback.call(req, &params, wood.data()).await)
+}
+
+/// Gets stored in the extensions of options requests before the middleware
+/// is called, contains the methods for which a route exists.
+pub(crate) struct AllowedMethods(pub Vec<Method>);
+
+/// Answers options requests with the methods which exist for the path.
+///
+/// Returns 405 with an `Allow` header if the path exists for other methods,
+/// or else 404.
+fn not_found(wood: &Wood, req: &Request) -> Response {
+	let uri = req.header().uri();
+	let allowed = wood.routes().allowed_methods(uri.host(), uri.path());
+	if allowed.is_empty() {
+		return StatusCode::NOT_FOUND.into();
+	}
+
+	let allow = allowed
+		.iter()
+		.map(Method::as_str)
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	let status_code = match req.header().method {
+		Method::OPTIONS => StatusCode::NO_CONTENT,
+		_ => StatusCode::METHOD_NOT_ALLOWED,
+	};
+
+	Response::builder()
+		.status_code(status_code)
+		.header(ALLOW, allow)
+		.build()
+}
+
 pub(crate) async fn route(
 	wood: &Wood,
 	req: &mut Request,
 ) -> Option<Result<Response, Error>> {
 	// first response
-	let (route, params) = wood
-		.routes()
-		.route(&req.header().method, req.header().uri().path())?;
+	let (route, params) = match wood.routes().route(req.header())? {
+		Ok(found) => found,
+		// guards of every matching route failed
+		Err(status_code) => return Some(Ok(status_code.into())),
+	};
 
 	let r = route.call(req, &params, wood.data()).await;
 
@@ -0,0 +1,472 @@
+//! Trusted proxies and the `Forwarded` and `X-Forwarded-*` headers.
+//!
+//! If a request is received from a trusted proxy the address of the client,
+//! the scheme and the host get read from the `X-Forwarded-For`,
+//! `X-Forwarded-Proto` and `X-Forwarded-Host` headers or, if configured with
+//! `TrustedProxies::header`, from the `Forwarded` header. The other headers
+//! are ignored since the proxy might pass them on from the client.
+//!
+//! `RequestHeader::address` then returns the address of the client and
+//! `RequestHeader::to_url` the url the client used. `RequestHeader::peer`
+//! still returns the address of the proxy.
+//!
+//! ## Example
+//! ```no_run
+//! # use fire_http as fire;
+//! use fire::forwarded::{ForwardedHeader, TrustedProxies};
+//!
+//! # async fn run() {
+//! let cidrs = ["10.0.0.0/8", "127.0.0.1"].map(|c| c.parse().unwrap());
+//! let proxies =
+//!     TrustedProxies::cidrs(cidrs).header(ForwardedHeader::XForwarded);
+//!
+//! let mut server = fire::build("0.0.0.0:3000").await.unwrap();
+//! server.trusted_proxies(proxies);
+//! # }
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use hyper::http::uri::{Authority, Scheme};
+use hyper::HeaderMap;
+
+const FORWARDED: &str = "forwarded";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// The cidr could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid cidr")]
+pub struct InvalidCidr;
+
+/// A range of ip addresses like `10.0.0.0/8` or `2001:db8::/32`.
+///
+/// An address without a prefix length matches only itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+	addr: IpAddr,
+	prefix: u8,
+}
+
+impl IpCidr {
+	/// Creates a new cidr, returns an error if the prefix is longer than
+	/// the address.
+	pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, InvalidCidr> {
+		let max = if addr.is_ipv4() { 32 } else { 128 };
+		if prefix > max {
+			return Err(InvalidCidr);
+		}
+
+		Ok(Self { addr, prefix })
+	}
+
+	/// Returns true if the address is part of this range.
+	pub fn contains(&self, addr: IpAddr) -> bool {
+		match (self.addr, to_canonical(addr)) {
+			(IpAddr::V4(net), IpAddr::V4(addr)) => mask_eq(
+				u32::from(net).into(),
+				u32::from(addr).into(),
+				32,
+				self.prefix,
+			),
+			(IpAddr::V6(net), IpAddr::V6(addr)) => {
+				mask_eq(u128::from(net), u128::from(addr), 128, self.prefix)
+			}
+			_ => false,
+		}
+	}
+}
+
+fn mask_eq(a: u128, b: u128, bits: u8, prefix: u8) -> bool {
+	let shift = bits - prefix;
+	// a prefix of zero matches everything and a shift by 128 would overflow
+	prefix == 0 || (a >> shift) == (b >> shift)
+}
+
+/// Ipv4 addresses mapped to ipv6 are treated as ipv4.
+fn to_canonical(addr: IpAddr) -> IpAddr {
+	match addr {
+		IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+			Some(v4) => IpAddr::V4(v4),
+			None => addr,
+		},
+		v4 => v4,
+	}
+}
+
+impl From<IpAddr> for IpCidr {
+	fn from(addr: IpAddr) -> Self {
+		let prefix = if addr.is_ipv4() { 32 } else { 128 };
+		Self { addr, prefix }
+	}
+}
+
+impl FromStr for IpCidr {
+	type Err = InvalidCidr;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once('/') {
+			Some((addr, prefix)) => Self::new(
+				addr.parse().map_err(|_| InvalidCidr)?,
+				prefix.parse().map_err(|_| InvalidCidr)?,
+			),
+			None => {
+				s.parse::<IpAddr>().map(Into::into).map_err(|_| InvalidCidr)
+			}
+		}
+	}
+}
+
+impl fmt::Display for IpCidr {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}/{}", self.addr, self.prefix)
+	}
+}
+
+/// The headers the trusted proxies send.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForwardedHeader {
+	/// The standard `Forwarded` header.
+	Forwarded,
+	/// The `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host`
+	/// headers.
+	#[default]
+	XForwarded,
+}
+
+/// Which peers are trusted to send `Forwarded` and `X-Forwarded-*`
+/// headers.
+#[derive(Debug, Clone)]
+pub struct TrustedProxies {
+	inner: Inner,
+	header: ForwardedHeader,
+}
+
+#[derive(Debug, Clone)]
+enum Inner {
+	Cidrs(Vec<IpCidr>),
+	Hops(usize),
+}
+
+impl TrustedProxies {
+	/// Trusts every proxy whose address is in one of the ranges.
+	///
+	/// The client is the first address, going from the nearest proxy back,
+	/// which is not trusted.
+	pub fn cidrs(cidrs: impl IntoIterator<Item = IpCidr>) -> Self {
+		Self {
+			inner: Inner::Cidrs(cidrs.into_iter().collect()),
+			header: ForwardedHeader::default(),
+		}
+	}
+
+	/// Trusts the given amount of proxies in front of the server, without
+	/// looking at their addresses.
+	///
+	/// ## Warning
+	/// Only use this if clients cannot connect to the server without
+	/// passing through all proxies.
+	pub fn hops(hops: usize) -> Self {
+		Self {
+			inner: Inner::Hops(hops),
+			header: ForwardedHeader::default(),
+		}
+	}
+
+	/// Sets the headers the proxies send, the default is
+	/// `ForwardedHeader::XForwarded`.
+	///
+	/// Only these headers are read, the proxies need to overwrite or append
+	/// to them, headers of the other kind might come from the client.
+	pub fn header(mut self, header: ForwardedHeader) -> Self {
+		self.header = header;
+		self
+	}
+
+	fn is_trusted(&self, addr: IpAddr) -> bool {
+		match &self.inner {
+			Inner::Cidrs(cidrs) => cidrs.iter().any(|c| c.contains(addr)),
+			Inner::Hops(hops) => *hops > 0,
+		}
+	}
+
+	/// Returns the index of the client in the chain of forwarded nodes.
+	///
+	/// The peer is not part of the chain and is already trusted.
+	fn client_index(&self, chain: &[Node]) -> usize {
+		match &self.inner {
+			Inner::Cidrs(cidrs) => chain
+				.iter()
+				.rposition(|node| match node.addr {
+					Some(addr) => !cidrs.iter().any(|c| c.contains(addr.ip())),
+					// an unknown node cannot be trusted
+					None => true,
+				})
+				.unwrap_or(0),
+			// the peer is the first hop
+			Inner::Hops(hops) => chain.len().saturating_sub(*hops),
+		}
+	}
+
+	/// Resolves the forwarded infos of a request received from the peer.
+	///
+	/// Returns None if the peer is not trusted or the request does not
+	/// contain any forwarded headers.
+	pub(crate) fn resolve(
+		&self,
+		peer: Option<SocketAddr>,
+		headers: &HeaderMap,
+	) -> Option<Forwarded> {
+		// unix domain sockets can only be reached locally
+		if !peer.map_or(true, |p| self.is_trusted(p.ip())) {
+			return None;
+		}
+
+		let chain = match self.header {
+			ForwardedHeader::Forwarded => forwarded_chain(headers),
+			ForwardedHeader::XForwarded => x_forwarded_chain(headers),
+		};
+
+		if chain.is_empty() {
+			return None;
+		}
+
+		let node = &chain[self.client_index(&chain)];
+
+		Some(Forwarded {
+			client: node.addr,
+			scheme: node.proto.clone(),
+			host: node.host.clone(),
+		})
+	}
+}
+
+/// The infos a trusted proxy sent about the client.
+#[derive(Debug, Clone)]
+pub(crate) struct Forwarded {
+	pub client: Option<SocketAddr>,
+	pub scheme: Option<Scheme>,
+	pub host: Option<Authority>,
+}
+
+/// One hop in the forwarded chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Node {
+	addr: Option<SocketAddr>,
+	proto: Option<Scheme>,
+	host: Option<Authority>,
+}
+
+fn header_values<'a>(
+	headers: &'a HeaderMap,
+	key: &str,
+) -> impl Iterator<Item = &'a str> {
+	headers
+		.get_all(key)
+		.into_iter()
+		.filter_map(|v| v.to_str().ok())
+		.flat_map(|v| v.split(','))
+		.map(str::trim)
+}
+
+/// Parses `Forwarded: for=192.0.2.60;proto=http, for="[2001:db8::17]:4711"`.
+fn forwarded_chain(headers: &HeaderMap) -> Vec<Node> {
+	header_values(headers, FORWARDED)
+		.map(|element| {
+			let mut node = Node::default();
+
+			for pair in element.split(';') {
+				let Some((key, value)) = pair.trim().split_once('=') else {
+					continue;
+				};
+				let value = value.trim_matches('"');
+
+				match key.to_ascii_lowercase().as_str() {
+					"for" => node.addr = parse_node(value),
+					"proto" => node.proto = parse_proto(value),
+					"host" => node.host = value.parse().ok(),
+					_ => {}
+				}
+			}
+
+			node
+		})
+		.collect()
+}
+
+/// Parses the `X-Forwarded-*` headers.
+///
+/// Only `X-Forwarded-For` can contain multiple values, the proto and host are
+/// applied to the client.
+fn x_forwarded_chain(headers: &HeaderMap) -> Vec<Node> {
+	let mut chain: Vec<_> = header_values(headers, X_FORWARDED_FOR)
+		.map(|addr| Node {
+			addr: parse_node(addr),
+			..Default::default()
+		})
+		.collect();
+
+	let proto = header_values(headers, X_FORWARDED_PROTO)
+		.next()
+		.and_then(parse_proto);
+	let host = header_values(headers, X_FORWARDED_HOST)
+		.next()
+		.and_then(|h| h.parse().ok());
+
+	if chain.is_empty() && (proto.is_some() || host.is_some()) {
+		chain.push(Node::default());
+	}
+
+	for node in &mut chain {
+		node.proto.clone_from(&proto);
+		node.host.clone_from(&host);
+	}
+
+	chain
+}
+
+/// Parses `192.0.2.60`, `192.0.2.60:80`, `2001:db8::1` or `[2001:db8::1]:80`.
+///
+/// The port is zero if it is unknown.
+fn parse_node(s: &str) -> Option<SocketAddr> {
+	if let Ok(addr) = s.parse::<SocketAddr>() {
+		return Some(addr);
+	}
+
+	let ip = s
+		.strip_prefix('[')
+		.and_then(|s| s.strip_suffix(']'))
+		.unwrap_or(s);
+	ip.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 0))
+}
+
+fn parse_proto(s: &str) -> Option<Scheme> {
+	match s.to_ascii_lowercase().as_str() {
+		"http" => Some(Scheme::HTTP),
+		"https" => Some(Scheme::HTTPS),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use hyper::header::HeaderValue;
+
+	fn headers(values: &[(&'static str, &'static str)]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for (k, v) in values {
+			headers.append(*k, HeaderValue::from_static(v));
+		}
+		headers
+	}
+
+	fn peer(s: &str) -> Option<SocketAddr> {
+		Some(s.parse().unwrap())
+	}
+
+	#[test]
+	fn cidr() {
+		let cidr: IpCidr = "10.0.0.0/8".parse().unwrap();
+		assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+		assert!(cidr.contains("::ffff:10.1.2.3".parse().unwrap()));
+		assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+
+		let cidr: IpCidr = "2001:db8::/32".parse().unwrap();
+		assert!(cidr.contains("2001:db8::1".parse().unwrap()));
+		assert!(!cidr.contains("2001:db9::1".parse().unwrap()));
+
+		let cidr: IpCidr = "0.0.0.0/0".parse().unwrap();
+		assert!(cidr.contains("1.2.3.4".parse().unwrap()));
+
+		let cidr: IpCidr = "127.0.0.1".parse().unwrap();
+		assert!(cidr.contains("127.0.0.1".parse().unwrap()));
+		assert!(!cidr.contains("127.0.0.2".parse().unwrap()));
+
+		assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+		assert!("10.0.0/8".parse::<IpCidr>().is_err());
+	}
+
+	#[test]
+	fn forwarded() {
+		let proxies = TrustedProxies::cidrs(["10.0.0.0/8".parse().unwrap()])
+			.header(ForwardedHeader::Forwarded);
+		let headers = headers(&[(
+			"forwarded",
+			"for=192.0.2.60;proto=https;host=example.com, \
+			for=\"[2001:db8::17]:4711\";proto=http, for=10.0.0.2",
+		)]);
+
+		let fwd = proxies.resolve(peer("10.0.0.1:80"), &headers).unwrap();
+		assert_eq!(fwd.client, peer("[2001:db8::17]:4711"));
+		assert_eq!(fwd.scheme, Some(Scheme::HTTP));
+		assert!(fwd.host.is_none());
+
+		// untrusted peer
+		assert!(proxies.resolve(peer("1.2.3.4:80"), &headers).is_none());
+
+		let proxies =
+			TrustedProxies::hops(3).header(ForwardedHeader::Forwarded);
+		let fwd = proxies.resolve(peer("1.2.3.4:80"), &headers).unwrap();
+		assert_eq!(fwd.client, peer("192.0.2.60:0"));
+		assert_eq!(fwd.scheme, Some(Scheme::HTTPS));
+		assert_eq!(fwd.host.unwrap(), "example.com");
+
+		// more hops than nodes
+		let proxies =
+			TrustedProxies::hops(10).header(ForwardedHeader::Forwarded);
+		let fwd = proxies.resolve(peer("1.2.3.4:80"), &headers).unwrap();
+		assert_eq!(fwd.client, peer("192.0.2.60:0"));
+
+		let proxies = TrustedProxies::hops(0);
+		assert!(proxies.resolve(peer("1.2.3.4:80"), &headers).is_none());
+	}
+
+	#[test]
+	fn x_forwarded() {
+		let proxies = TrustedProxies::cidrs(["10.0.0.0/8".parse().unwrap()]);
+		let headers = headers(&[
+			("x-forwarded-for", "192.0.2.60, 10.0.0.3"),
+			("x-forwarded-for", "10.0.0.2"),
+			("x-forwarded-proto", "https"),
+			("x-forwarded-host", "example.com:8080"),
+		]);
+
+		let fwd = proxies.resolve(peer("10.0.0.1:80"), &headers).unwrap();
+		assert_eq!(fwd.client, peer("192.0.2.60:0"));
+		assert_eq!(fwd.scheme, Some(Scheme::HTTPS));
+		assert_eq!(fwd.host.unwrap(), "example.com:8080");
+
+		let proxies = TrustedProxies::hops(1);
+		let fwd = proxies.resolve(peer("10.0.0.1:80"), &headers).unwrap();
+		assert_eq!(fwd.client, peer("10.0.0.2:0"));
+	}
+
+	#[test]
+	fn other_header_ignored() {
+		// the proxy only appends x-forwarded-for and passes on the forwarded
+		// header of the client
+		let spoofed = headers(&[
+			("x-forwarded-for", "203.0.113.9"),
+			("forwarded", "for=1.2.3.4;host=evil.example"),
+		]);
+
+		let proxies = TrustedProxies::cidrs(["127.0.0.1".parse().unwrap()]);
+		let fwd = proxies.resolve(peer("127.0.0.1:80"), &spoofed).unwrap();
+		assert_eq!(fwd.client, peer("203.0.113.9:0"));
+		assert!(fwd.host.is_none());
+
+		let only_forwarded = headers(&[("forwarded", "for=1.2.3.4")]);
+		assert!(proxies
+			.resolve(peer("127.0.0.1:80"), &only_forwarded)
+			.is_none());
+
+		let proxies = proxies.header(ForwardedHeader::Forwarded);
+		let only_x = headers(&[("x-forwarded-for", "203.0.113.9")]);
+		assert!(proxies.resolve(peer("127.0.0.1:80"), &only_x).is_none());
+	}
+}
@@ -104,7 +104,12 @@ impl Caching {
 	pub fn if_none_match(&self, header: &RequestHeader) -> bool {
 		header
 			.value(IF_NONE_MATCH)
-			.map(|none_match| none_match.len() == 30 && self.etag == none_match)
+			.map(|none_match| {
+				// compression makes the etag weak
+				let none_match =
+					none_match.strip_prefix("W/").unwrap_or(none_match);
+				none_match.len() == 30 && self.etag == none_match
+			})
 			.unwrap_or(false)
 	}
 
@@ -1,10 +1,10 @@
 use super::static_files::CachingBuilder;
 use super::{file, partial_file, Caching, Range};
 use crate::header::Method;
-use crate::into::IntoResponse;
-use crate::routes::{ParamsNames, PathParams, RoutePath};
+use crate::into::{IntoResponse, IntoRoute};
+use crate::routes::{ParamsNames, PathParams, Route, RouteKind, RoutePath};
 use crate::util::PinnedFuture;
-use crate::{Error, IntoRoute, Request, Resources, Response, Route};
+use crate::{Error, Request, Resources, Response};
 
 use std::io;
 use std::time::Duration;
@@ -125,6 +125,10 @@ pub struct MemoryFileRoute {
 impl Route for MemoryFileRoute {
 	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {}
 
+	fn kind(&self) -> RouteKind {
+		RouteKind::StaticFiles
+	}
+
 	fn path(&self) -> RoutePath {
 		RoutePath {
 			method: Some(Method::GET),
@@ -4,7 +4,7 @@ use super::{Caching, IntoPathBuf, Range};
 use crate::error::ClientErrorKind;
 use crate::header::{Method, StatusCode};
 use crate::into::{IntoResponse, IntoRoute};
-use crate::routes::{ParamsNames, PathParams, Route, RoutePath};
+use crate::routes::{ParamsNames, PathParams, Route, RouteKind, RoutePath};
 use crate::util::PinnedFuture;
 use crate::{Error, Request, Resources, Response};
 
@@ -202,6 +202,10 @@ pub struct StaticFilesRoute {
 impl Route for StaticFilesRoute {
 	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {}
 
+	fn kind(&self) -> RouteKind {
+		RouteKind::StaticFiles
+	}
+
 	fn path(&self) -> RoutePath {
 		RoutePath {
 			method: Some(Method::GET),
@@ -219,8 +223,16 @@ impl Route for StaticFilesRoute {
 		let caching = self.caching.clone();
 
 		PinnedFuture::new(async move {
-			let res_path_buf =
-				req.header().uri().path()[uri.len()..].into_path_buf();
+			let rem = &req.header().uri().path()[uri.len()..];
+
+			// directories are not served, so a trailing slash never matches
+			// a file, the router redirects to the file depending on the
+			// trailing slash policy
+			if rem.ends_with('/') {
+				return Err(Error::empty(ClientErrorKind::NotFound));
+			}
+
+			let res_path_buf = rem.into_path_buf();
 
 			// validate path buf
 			// if path is a directory serve_file will return NotFound
@@ -371,6 +383,10 @@ pub struct StaticFileRoute {
 impl Route for StaticFileRoute {
 	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {}
 
+	fn kind(&self) -> RouteKind {
+		RouteKind::StaticFiles
+	}
+
 	fn path(&self) -> RoutePath {
 		RoutePath {
 			method: Some(Method::GET),
@@ -10,13 +10,12 @@ use resources::Resources;
 pub mod state;
 
 pub mod routes;
-use routes::{Catcher, ParamsNames, RawRoute, Route, Routes};
+use routes::{RouteInfo, TrailingSlash};
 
 #[macro_use]
 pub mod util;
 
 pub mod into;
-use into::IntoRoute;
 
 pub mod error;
 pub use error::{Error, Result};
@@ -27,8 +26,33 @@ pub use extractor::Res;
 mod server;
 use server::Server;
 
+pub mod listener;
+use listener::Listener;
+
+pub mod proxy_protocol;
+
+pub mod forwarded;
+use forwarded::TrustedProxies;
+
+pub mod reload;
+use reload::ReloadHandle;
+
+mod registry;
+pub use registry::Registry;
+
+pub mod shutdown;
+use shutdown::{ShutdownHandle, ShutdownReport};
+
+pub mod protocol;
+use protocol::ProtocolConfig;
+
+pub mod cors;
+
+pub mod connections;
+use connections::{ConnectionLimits, ConnectionStats, Connections, Overflow};
+
 mod fire;
-use fire::{RequestConfigs, Wood};
+use fire::{Hearth, RequestConfigs, Wood};
 use tracing::info;
 
 #[cfg(feature = "fs")]
@@ -43,21 +67,31 @@ pub mod json;
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 pub mod ws;
 
+#[cfg(feature = "compression")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+pub mod compression;
+
 #[cfg(feature = "graphql")]
 #[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
 pub mod graphql;
 
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub mod tls;
+
 pub mod service {
 	pub use crate::server::FireService;
 }
 
-use std::any::Any;
-use std::net::SocketAddr;
+use std::future::{pending, Future};
+use std::net::{IpAddr, SocketAddr};
+use std::ops::{Deref, DerefMut};
+use std::panic::resume_unwind;
 use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::net::ToSocketAddrs;
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
 
 pub use types;
 pub use types::{body, header, Body, Request, Response};
@@ -69,12 +103,41 @@ pub async fn build(addr: impl ToSocketAddrs) -> Result<FireBuilder> {
 	FireBuilder::new(addr).await
 }
 
+/// Prepares a server which accepts connections from an already bound
+/// listener.
+pub fn build_with_listener(listener: Listener) -> FireBuilder {
+	FireBuilder::from_bind(Bind::Listener(listener))
+}
+
+async fn lookup_addr(addr: impl ToSocketAddrs) -> Result<SocketAddr> {
+	tokio::net::lookup_host(addr)
+		.await
+		.map_err(Error::from_server_error)?
+		.next()
+		.ok_or_else(|| {
+			Error::from_server_error("address could not be resolved")
+		})
+}
+
+enum Bind {
+	Addr {
+		addr: SocketAddr,
+		name: Option<String>,
+	},
+	Listener(Listener),
+}
+
 /// `FireBuilder` gathers all materials needed to light a fire (start a server).
 pub struct FireBuilder {
-	addr: SocketAddr,
-	resources: Resources,
-	routes: Routes,
+	binds: Vec<Bind>,
+	registry: Registry,
 	configs: RequestConfigs,
+	connection_limits: ConnectionLimits,
+	protocol: ProtocolConfig,
+	trusted_proxies: Option<TrustedProxies>,
+	shutdown_timeout: Duration,
+	#[cfg(feature = "tls")]
+	tls: Option<tls::TlsConfig>,
 }
 
 impl FireBuilder {
@@ -82,91 +145,206 @@ impl FireBuilder {
 	where
 		A: ToSocketAddrs,
 	{
-		let addr = tokio::net::lookup_host(addr)
-			.await
-			.map_err(Error::from_server_error)?
-			.next()
-			.unwrap();
-		Ok(Self {
-			addr,
-			resources: Resources::new(),
-			routes: Routes::new(),
-			configs: RequestConfigs::new(),
-		})
+		let addr = lookup_addr(addr).await?;
+		Ok(Self::from_bind(Bind::Addr { addr, name: None }))
 	}
 
-	/// Returns a reference to the current data.
-	pub fn data(&self) -> &Resources {
-		&self.resources
+	fn from_bind(bind: Bind) -> Self {
+		Self {
+			binds: vec![bind],
+			registry: Registry::new(),
+			configs: RequestConfigs::new(),
+			connection_limits: ConnectionLimits::new(),
+			protocol: ProtocolConfig::new(),
+			trusted_proxies: None,
+			shutdown_timeout: shutdown::DEFAULT_SHUTDOWN_TIMEOUT,
+			#[cfg(feature = "tls")]
+			tls: None,
+		}
 	}
 
-	pub fn add_data<D>(&mut self, data: D)
-	where
-		D: Any + Send + Sync,
-	{
-		self.resources.insert(data);
+	/// Sets the request size limit. The default is 4 kilobytes.
+	///
+	/// This can be changed in every Route, for example with
+	/// `#[post("/upload", size_limit = 50_000_000)]`.
+	///
+	/// ## Panics
+	/// If the size is zero.
+	pub fn request_size_limit(&mut self, size_limit: usize) {
+		self.configs.size_limit(size_limit)
 	}
 
-	/// Adds a `RawRoute` to the fire.
-	pub fn add_raw_route<R>(&mut self, route: R)
-	where
-		R: RawRoute + 'static,
-	{
-		let path = route.path();
-		let names = ParamsNames::parse(&path.path);
-		route.validate_requirements(&names, &self.resources);
-		self.routes.push_raw(path, route)
+	/// Sets the request timeout. The default is 60 seconds.
+	///
+	/// This can be changed in every Route, for example with
+	/// `#[post("/upload", timeout = 300)]` in seconds.
+	pub fn request_timeout(&mut self, timeout: Duration) {
+		self.configs.timeout(timeout)
 	}
 
-	/// Adds a `Route` to the fire.
-	pub fn add_route<R>(&mut self, route: R)
-	where
-		R: IntoRoute + 'static,
-	{
-		let route = route.into_route();
-		let path = route.path();
-		let names = ParamsNames::parse(&path.path);
-		route.validate_requirements(&names, &self.resources);
-		self.routes.push(path, route)
+	/// Sets how requests are handled whose path only differs by a trailing
+	/// slash from a route. The default is `TrailingSlash::Strict`.
+	pub fn trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+		self.configs.trailing_slash(trailing_slash)
 	}
 
-	/// Adds a `Catcher` to the fire.
-	pub fn add_catcher<C>(&mut self, catcher: C)
-	where
-		C: Catcher + 'static,
-	{
-		catcher.validate_data(&self.resources);
-		self.routes.push_catcher(catcher)
+	/// Sets the maximum amount of connections which can be open at the same
+	/// time over all listeners. The default is no limit.
+	///
+	/// Upgraded connections like websockets count until they are closed.
+	///
+	/// What happens with connections over the limit can be changed with
+	/// `connection_overflow`.
+	///
+	/// ## Panics
+	/// If the max is zero.
+	pub fn max_connections(&mut self, max: usize) {
+		self.connection_limits.max(max)
 	}
 
-	/// Sets the request size limit. The default is 4 kilobytes.
+	/// Sets the maximum amount of connections a single ip address can have
+	/// open at the same time. The default is no limit.
 	///
-	/// This can be changed in every Route.
+	/// Connections over the limit are closed immediately. Connections on a
+	/// unix domain socket are not affected.
 	///
 	/// ## Panics
-	/// If the size is zero.
-	pub fn request_size_limit(&mut self, size_limit: usize) {
-		self.configs.size_limit(size_limit)
+	/// If the max is zero.
+	pub fn max_connections_per_ip(&mut self, max: usize) {
+		self.connection_limits.max_per_ip(max)
 	}
 
-	/// Sets the request timeout. The default is 60 seconds.
+	/// Sets what happens with new connections if `max_connections` is
+	/// reached. The default is `Overflow::Wait`.
+	pub fn connection_overflow(&mut self, overflow: Overflow) {
+		self.connection_limits.overflow(overflow)
+	}
+
+	/// Sets the http/1 and http/2 settings used for every connection.
 	///
-	/// This can be changed in every Route.
-	pub fn request_timeout(&mut self, timeout: Duration) {
-		self.configs.timeout(timeout)
+	/// Returns an error if a setting is set for a protocol whose feature is
+	/// not enabled.
+	pub fn protocol(&mut self, config: ProtocolConfig) -> Result<()> {
+		config.validate().map_err(Error::from_server_error)?;
+		self.protocol = config;
+		Ok(())
+	}
+
+	/// Sets which proxies are trusted to send `Forwarded` and
+	/// `X-Forwarded-*` headers. By default no proxy is trusted.
+	///
+	/// For requests from a trusted proxy `RequestHeader::address` returns the
+	/// address of the client and `RequestHeader::to_url` the url the client
+	/// used.
+	pub fn trusted_proxies(&mut self, proxies: TrustedProxies) {
+		self.trusted_proxies = Some(proxies);
+	}
+
+	/// Adds another address on which the same routes and resources are
+	/// served.
+	///
+	/// The address gets bound when calling `build`, every request received
+	/// on it has the listener name `name`, see `RequestHeader::listener`.
+	///
+	/// To serve https on the address use `add_listener` with
+	/// `Listener::with_tls`.
+	pub async fn add_address(
+		&mut self,
+		name: impl Into<String>,
+		addr: impl ToSocketAddrs,
+	) -> Result<()> {
+		let addr = lookup_addr(addr).await?;
+		self.binds.push(Bind::Addr {
+			addr,
+			name: Some(name.into()),
+		});
+		Ok(())
+	}
+
+	/// Adds another listener on which the same routes and resources are
+	/// served.
+	///
+	/// Give every listener a distinct name with `Listener::with_name`, so
+	/// routes and catchers can check with `RequestHeader::listener` on which
+	/// one a request was received.
+	pub fn add_listener(&mut self, listener: Listener) {
+		self.binds.push(Bind::Listener(listener));
+	}
+
+	/// Sets how long a graceful shutdown waits for open connections to
+	/// finish before they get closed forcefully. The default is 30 seconds.
+	pub fn shutdown_timeout(&mut self, timeout: Duration) {
+		self.shutdown_timeout = timeout;
+	}
+
+	/// Serves https with the given certificate and private key on the
+	/// address or listener the builder was created with.
+	///
+	/// Other listeners can serve https with `Listener::with_tls`.
+	///
+	/// Http/2 and http/1.1 get negotiated with alpn, depending on which
+	/// features are enabled.
+	#[cfg(feature = "tls")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+	pub fn tls(&mut self, config: tls::TlsConfig) {
+		self.tls = Some(config);
 	}
 
 	/// Binds to the address and prepares to serve requests.
 	///
 	/// You need to call ignite on the `Fire` so that it starts handling
 	/// requests.
-	pub async fn build(self) -> Result<Fire> {
-		let wood =
-			Arc::new(Wood::new(self.resources, self.routes, self.configs));
-
-		let server = Server::bind(self.addr, wood.clone()).await?;
+	pub async fn build(mut self) -> Result<Fire> {
+		self.check_routes().map_err(Error::from_server_error)?;
+
+		let binds = std::mem::take(&mut self.binds);
+		let protocol = std::mem::take(&mut self.protocol);
+		let shutdown_timeout = self.shutdown_timeout;
+		#[cfg(feature = "tls")]
+		let mut tls = self.tls.take();
+
+		let hearth = Arc::new(self.into_hearth());
+
+		let builder = Arc::new(protocol.builder());
+
+		let mut servers = Vec::with_capacity(binds.len());
+		for bind in binds {
+			#[allow(unused_mut)]
+			let mut listener = match bind {
+				Bind::Addr { addr, name: None } => Listener::bind(addr).await?,
+				Bind::Addr {
+					addr,
+					name: Some(name),
+				} => Listener::bind(addr).await?.with_name(name),
+				Bind::Listener(listener) => listener,
+			};
+
+			// the tls config of the builder belongs to the first listener
+			#[cfg(feature = "tls")]
+			if let Some(config) = tls.take() {
+				listener = listener.with_tls(config);
+			}
+			#[cfg(feature = "tls")]
+			let tls = listener
+				.take_tls()
+				.map(tls::TlsConfig::into_acceptor)
+				.transpose()?;
+
+			servers.push(Server::new(
+				listener,
+				hearth.clone(),
+				builder.clone(),
+				#[cfg(feature = "tls")]
+				tls.clone(),
+			));
+		}
 
-		Ok(Fire { wood, server })
+		Ok(Fire {
+			hearth,
+			servers,
+			shutdown: ShutdownHandle::new(),
+			shutdown_timeout,
+		})
 	}
 
 	/// Ignites the fire, which starts the server.
@@ -178,6 +356,19 @@ impl FireBuilder {
 		fire.ignite().await
 	}
 
+	/// Ignites the fire and shuts it down gracefully once the signal
+	/// completes.
+	pub async fn ignite_with_shutdown<F>(
+		self,
+		signal: F,
+	) -> Result<ShutdownReport>
+	where
+		F: Future<Output = ()>,
+	{
+		let fire = self.build().await?;
+		fire.ignite_with_shutdown(signal).await
+	}
+
 	/// Ignites the fire, and spawns it on a new tokio task.
 	///
 	/// ## Note
@@ -193,53 +384,213 @@ impl FireBuilder {
 	/// Creating a `FirePit` might be useful for testing or if you want to
 	/// manually create a server.
 	pub fn into_pit(self) -> FirePit {
-		let wood =
-			Arc::new(Wood::new(self.resources, self.routes, self.configs));
+		FirePit::new(Arc::new(self.into_hearth()))
+	}
+
+	fn into_hearth(self) -> Hearth {
+		Hearth::new(
+			Wood::new(
+				self.registry.resources,
+				self.registry.routes,
+				self.configs,
+			),
+			Connections::new(self.connection_limits),
+			self.trusted_proxies,
+		)
+	}
+}
+
+impl Deref for FireBuilder {
+	type Target = Registry;
+
+	fn deref(&self) -> &Registry {
+		&self.registry
+	}
+}
 
-		FirePit { wood }
+impl DerefMut for FireBuilder {
+	fn deref_mut(&mut self) -> &mut Registry {
+		&mut self.registry
 	}
 }
 
 /// A Fire that is ready to be ignited.
 pub struct Fire {
-	wood: Arc<Wood>,
-	server: Server,
+	hearth: Arc<Hearth>,
+	// contains at least one server
+	servers: Vec<Server>,
+	shutdown: ShutdownHandle,
+	shutdown_timeout: Duration,
 }
 
 impl Fire {
+	/// Returns the local address if the first listener listens on tcp.
 	pub fn local_addr(&self) -> Option<SocketAddr> {
-		self.server.local_addr().ok()
+		self.listener().local_addr()
+	}
+
+	/// Returns the first listener the server accepts connections from.
+	pub fn listener(&self) -> &Listener {
+		self.servers[0].listener()
+	}
+
+	/// Returns every listener the server accepts connections from.
+	pub fn listeners(&self) -> impl Iterator<Item = &Listener> {
+		self.servers.iter().map(Server::listener)
 	}
 
 	pub fn pit(&self) -> FirePit {
-		FirePit {
-			wood: self.wood.clone(),
-		}
+		FirePit::new(self.hearth.clone())
 	}
 
+	/// Returns a handle which can be used to swap the routes and resources
+	/// while the server is running.
+	pub fn reload_handle(&self) -> ReloadHandle {
+		ReloadHandle::new(self.hearth.clone())
+	}
+
+	/// Returns the current usage of connections over all listeners.
+	pub fn connections(&self) -> ConnectionStats {
+		self.hearth.connections().stats()
+	}
+
+	/// Returns the amount of connections which are currently open from the
+	/// ip address.
+	pub fn connections_from(&self, ip: IpAddr) -> usize {
+		self.hearth.connections().count_for_ip(ip)
+	}
+
+	/// Returns a handle which can be used to shutdown the server
+	/// gracefully.
+	pub fn shutdown_handle(&self) -> ShutdownHandle {
+		self.shutdown.clone()
+	}
+
+	/// Starts the server.
+	///
+	/// ## Note
+	/// This function runs until the shutdown gets triggered with a
+	/// `ShutdownHandle`.
 	pub async fn ignite(self) -> Result<()> {
-		info!("Running server on addr: {}", self.local_addr().unwrap());
+		self.ignite_with_shutdown(pending()).await.map(|_| ())
+	}
+
+	/// Starts the server and shuts it down gracefully once the signal
+	/// completes or the shutdown gets triggered with a `ShutdownHandle`.
+	///
+	/// New connections are not accepted anymore, open connections can finish
+	/// their in-flight requests until the shutdown timeout elapses.
+	pub async fn ignite_with_shutdown<F>(
+		self,
+		signal: F,
+	) -> Result<ShutdownReport>
+	where
+		F: Future<Output = ()>,
+	{
+		let shutdown = self.shutdown;
 
-		self.server.serve().await
+		let mut servers = JoinSet::new();
+		for server in self.servers {
+			info!("Running server on addr: {}", server.listener());
+
+			servers
+				.spawn(server.serve(shutdown.clone(), self.shutdown_timeout));
+		}
+
+		let serve = async {
+			let mut report = ShutdownReport {
+				drained: 0,
+				force_closed: 0,
+			};
+			let mut error = None;
+
+			while let Some(res) = servers.join_next().await {
+				let res = res.unwrap_or_else(|e| resume_unwind(e.into_panic()));
+
+				match res {
+					Ok(r) => {
+						report.drained += r.drained;
+						report.force_closed += r.force_closed;
+					}
+					Err(e) => {
+						// if one listener fails every other one should stop
+						// as well
+						shutdown.shutdown();
+						error.get_or_insert(e);
+					}
+				}
+			}
+
+			match error {
+				Some(e) => Err(e),
+				None => Ok(report),
+			}
+		};
+		tokio::pin!(serve);
+
+		tokio::select! {
+			res = &mut serve => return res,
+			_ = signal => shutdown.shutdown(),
+		}
+
+		serve.await
 	}
 }
 
+/// Gives access to the routes and resources of a `Fire`.
+///
+/// A `FirePit` keeps the routes and resources which were current when it was
+/// created, even if they get swapped with a `ReloadHandle`. A `FireService`
+/// created from it always uses the current ones.
 #[derive(Clone)]
 pub struct FirePit {
+	hearth: Arc<Hearth>,
 	wood: Arc<Wood>,
 }
 
 impl FirePit {
+	fn new(hearth: Arc<Hearth>) -> Self {
+		Self {
+			wood: hearth.wood(),
+			hearth,
+		}
+	}
+
 	pub fn data(&self) -> &Resources {
 		self.wood.data()
 	}
 
 	/// Routes the request to normal routes and returns their result.
 	///
-	/// Useful for tests and niche applications.
+	/// Useful for tests and niche applications. Middleware and catchers are
+	/// not called.
 	///
 	/// Returns None if no route was found matching the request.
 	pub async fn route(&self, req: &mut Request) -> Option<Result<Response>> {
 		fire::route(&self.wood, req).await
 	}
+
+	/// Returns every registered route in the order they were added.
+	pub fn routes(&self) -> &[RouteInfo] {
+		self.wood.routes().infos()
+	}
+
+	/// Returns the current usage of connections over all listeners.
+	///
+	/// Connections served with a `FireService` created by you are not
+	/// counted.
+	pub fn connections(&self) -> ConnectionStats {
+		self.hearth.connections().stats()
+	}
+
+	/// Returns the amount of connections which are currently open from the
+	/// ip address.
+	pub fn connections_from(&self, ip: IpAddr) -> usize {
+		self.hearth.connections().count_for_ip(ip)
+	}
+
+	/// Returns a handle which can be used to swap the routes and resources.
+	pub fn reload_handle(&self) -> ReloadHandle {
+		ReloadHandle::new(self.hearth.clone())
+	}
 }
@@ -0,0 +1,327 @@
+//! Listeners which accept connections for a `Fire`.
+
+use crate::header::PeerAddr;
+use crate::proxy_protocol::ProxyProtocol;
+#[cfg(feature = "tls")]
+use crate::tls::TlsConfig;
+use crate::{Error, Result};
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+
+const DEFAULT_NAME: &str = "default";
+
+/// A bound listener which can be served by a `Fire`.
+///
+/// Every listener has a name, which is `default` if not changed with
+/// `with_name`. Requests know with `RequestHeader::listener` on which listener
+/// they were received.
+///
+/// ## Example
+/// ```no_run
+/// # use fire_http as fire;
+/// use fire::listener::Listener;
+///
+/// # async fn run() {
+/// let listener = std::net::TcpListener::bind("127.0.0.1:3000").unwrap();
+/// let listener = Listener::from_std(listener).unwrap();
+///
+/// let mut server = fire::build_with_listener(listener);
+/// # }
+/// ```
+pub struct Listener {
+	inner: Inner,
+	name: Arc<str>,
+	proxy_protocol: Option<ProxyProtocol>,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsConfig>,
+}
+
+enum Inner {
+	Tcp(TcpListener),
+	#[cfg(unix)]
+	Unix(UnixListener),
+}
+
+impl Listener {
+	/// Binds a tcp listener to the address.
+	pub async fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+		TcpListener::bind(addr)
+			.await
+			.map(Self::from_tcp)
+			.map_err(Error::from_server_error)
+	}
+
+	/// Creates a listener from an already bound tcp listener.
+	///
+	/// This can be used with systemd socket activation or if the port
+	/// needs to be bound before privileges are dropped.
+	///
+	/// ## Note
+	/// This needs to be called inside of a tokio runtime.
+	pub fn from_std(listener: std::net::TcpListener) -> Result<Self> {
+		listener
+			.set_nonblocking(true)
+			.and_then(|_| TcpListener::from_std(listener))
+			.map(Self::from_tcp)
+			.map_err(Error::from_server_error)
+	}
+
+	/// Creates a listener from a tokio tcp listener.
+	pub fn from_tcp(listener: TcpListener) -> Self {
+		Self {
+			inner: Inner::Tcp(listener),
+			name: DEFAULT_NAME.into(),
+			proxy_protocol: None,
+			#[cfg(feature = "tls")]
+			tls: None,
+		}
+	}
+
+	/// Binds a unix domain socket to the path.
+	///
+	/// ## Note
+	/// Fails if the path already exists.
+	#[cfg(unix)]
+	#[cfg_attr(docsrs, doc(cfg(unix)))]
+	pub fn bind_unix(path: impl AsRef<Path>) -> Result<Self> {
+		UnixListener::bind(path)
+			.map(Self::from_unix)
+			.map_err(Error::from_server_error)
+	}
+
+	/// Creates a listener from an already bound unix domain socket.
+	///
+	/// ## Note
+	/// This needs to be called inside of a tokio runtime.
+	#[cfg(unix)]
+	#[cfg_attr(docsrs, doc(cfg(unix)))]
+	pub fn from_std_unix(
+		listener: std::os::unix::net::UnixListener,
+	) -> Result<Self> {
+		listener
+			.set_nonblocking(true)
+			.and_then(|_| UnixListener::from_std(listener))
+			.map(Self::from_unix)
+			.map_err(Error::from_server_error)
+	}
+
+	/// Creates a listener from a tokio unix listener.
+	#[cfg(unix)]
+	#[cfg_attr(docsrs, doc(cfg(unix)))]
+	pub fn from_unix(listener: UnixListener) -> Self {
+		Self {
+			inner: Inner::Unix(listener),
+			name: DEFAULT_NAME.into(),
+			proxy_protocol: None,
+			#[cfg(feature = "tls")]
+			tls: None,
+		}
+	}
+
+	/// Sets the name of the listener.
+	pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+		self.name = name.as_ref().into();
+		self
+	}
+
+	/// Returns the name of the listener.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub(crate) fn name_arc(&self) -> Arc<str> {
+		self.name.clone()
+	}
+
+	/// Expects connections to start with a PROXY protocol header.
+	///
+	/// The source address of the header is then used as the address of the
+	/// request.
+	pub fn with_proxy_protocol(mut self, mode: ProxyProtocol) -> Self {
+		self.proxy_protocol = Some(mode);
+		self
+	}
+
+	/// Returns the PROXY protocol mode if one was set.
+	pub fn proxy_protocol(&self) -> Option<ProxyProtocol> {
+		self.proxy_protocol
+	}
+
+	/// Serves https on this listener with the given certificate and private
+	/// key.
+	///
+	/// Http/2 and http/1.1 get negotiated with alpn, depending on which
+	/// features are enabled.
+	#[cfg(feature = "tls")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+	pub fn with_tls(mut self, config: TlsConfig) -> Self {
+		self.tls = Some(config);
+		self
+	}
+
+	#[cfg(feature = "tls")]
+	pub(crate) fn take_tls(&mut self) -> Option<TlsConfig> {
+		self.tls.take()
+	}
+
+	/// Returns the local address if this is a tcp listener.
+	pub fn local_addr(&self) -> Option<SocketAddr> {
+		match &self.inner {
+			Inner::Tcp(l) => l.local_addr().ok(),
+			#[cfg(unix)]
+			Inner::Unix(_) => None,
+		}
+	}
+
+	/// Returns the local path if this is a unix domain socket.
+	#[cfg(unix)]
+	#[cfg_attr(docsrs, doc(cfg(unix)))]
+	pub fn local_path(&self) -> Option<PathBuf> {
+		match &self.inner {
+			Inner::Tcp(_) => None,
+			Inner::Unix(l) => l
+				.local_addr()
+				.ok()
+				.and_then(|a| a.as_pathname().map(Path::to_path_buf)),
+		}
+	}
+
+	pub(crate) async fn accept(&self) -> io::Result<(Stream, PeerAddr)> {
+		match &self.inner {
+			Inner::Tcp(l) => {
+				let (stream, addr) = l.accept().await?;
+				Ok((Stream::Tcp(stream), PeerAddr::Tcp(addr)))
+			}
+			#[cfg(unix)]
+			Inner::Unix(l) => {
+				let (stream, addr) = l.accept().await?;
+				let path = addr.as_pathname().map(Path::to_path_buf);
+				Ok((Stream::Unix(stream), PeerAddr::Unix(path)))
+			}
+		}
+	}
+}
+
+impl fmt::Debug for Listener {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.inner {
+			Inner::Tcp(l) => f
+				.debug_struct("Tcp")
+				.field("name", &self.name)
+				.field("addr", &l.local_addr())
+				.finish(),
+			#[cfg(unix)]
+			Inner::Unix(l) => f
+				.debug_struct("Unix")
+				.field("name", &self.name)
+				.field("addr", &l.local_addr())
+				.finish(),
+		}
+	}
+}
+
+impl fmt::Display for Listener {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.inner {
+			Inner::Tcp(l) => match l.local_addr() {
+				Ok(addr) => write!(f, "{addr}"),
+				Err(_) => f.write_str("unknown tcp address"),
+			},
+			#[cfg(unix)]
+			Inner::Unix(_) => match self.local_path() {
+				Some(path) => write!(f, "unix:{}", path.display()),
+				None => f.write_str("unix:unnamed"),
+			},
+		}
+	}
+}
+
+/// A stream accepted by a `Listener`.
+pub(crate) enum Stream {
+	Tcp(TcpStream),
+	#[cfg(unix)]
+	Unix(UnixStream),
+}
+
+impl AsyncRead for Stream {
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut ReadBuf<'_>,
+	) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for Stream {
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8],
+	) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_flush(cx),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_shutdown(cx),
+		}
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		bufs: &[io::IoSlice<'_>],
+	) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+		}
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		match self {
+			Self::Tcp(s) => s.is_write_vectored(),
+			#[cfg(unix)]
+			Self::Unix(s) => s.is_write_vectored(),
+		}
+	}
+}
@@ -6,11 +6,12 @@ macro_rules! impl_res_extractor {
 			type Prepared = ();
 
 			$crate::extractor_validate!(|validate| {
-				assert!(
-					validate.resources.exists::<$ty>(),
-					"Resource {} does not exist",
-					stringify!($ty)
-				);
+				if !validate.resources.exists::<$ty>() {
+					return Err(format!(
+						"Resource {} does not exist",
+						stringify!($ty)
+					));
+				}
 			});
 
 			$crate::extractor_prepare!();
@@ -46,8 +47,11 @@ macro_rules! extractor_validate {
 		$crate::extractor_validate!(|_validate| {});
 	};
 	(|$validate:ident| $block:block) => {
-		fn validate($validate: $crate::extractor::Validate<'_>) {
+		fn validate(
+			$validate: $crate::extractor::Validate<'_>,
+		) -> std::result::Result<(), String> {
 			$block
+			Ok(())
 		}
 	};
 }
@@ -0,0 +1,363 @@
+//! Http/1 and http/2 protocol settings.
+//!
+//! Every setting which is not set keeps the default of hyper.
+//!
+//! ## Example
+//! ```no_run
+//! # use fire_http as fire;
+//! use fire::protocol::{Http1Config, ProtocolConfig};
+//! use std::time::Duration;
+//!
+//! # async fn run() {
+//! let timeout = Duration::from_secs(10);
+//! let http1 = Http1Config::new().header_read_timeout(timeout);
+//! let config = ProtocolConfig::new().http1(http1.max_headers(64));
+//!
+//! let mut server = fire::build("0.0.0.0:3000").await.unwrap();
+//! server.protocol(config).unwrap();
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+
+/// A setting was configured for a protocol which is not enabled.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProtocolError {
+	#[error(
+		"{protocol} setting `{setting}` was set but the feature \
+		{protocol:?} is not enabled"
+	)]
+	FeatureDisabled {
+		protocol: &'static str,
+		setting: &'static str,
+	},
+}
+
+/// Settings for http/1 connections.
+///
+/// Requires the `http1` feature to be applied.
+#[derive(Debug, Clone, Default)]
+pub struct Http1Config {
+	keep_alive: Option<bool>,
+	header_read_timeout: Option<Duration>,
+	max_headers: Option<usize>,
+	max_buf_size: Option<usize>,
+	half_close: Option<bool>,
+	title_case_headers: Option<bool>,
+	preserve_header_case: Option<bool>,
+}
+
+impl Http1Config {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets whether connections should be kept alive after a response.
+	pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+		self.keep_alive = Some(keep_alive);
+		self
+	}
+
+	/// Sets how long a client can take to send all headers of a request,
+	/// after which the connection gets closed.
+	pub fn header_read_timeout(mut self, timeout: Duration) -> Self {
+		self.header_read_timeout = Some(timeout);
+		self
+	}
+
+	/// Sets the maximum amount of headers a request can contain.
+	pub fn max_headers(mut self, max: usize) -> Self {
+		self.max_headers = Some(max);
+		self
+	}
+
+	/// Sets the maximum size of the read buffer, which limits the size of
+	/// the request head.
+	pub fn max_buf_size(mut self, max: usize) -> Self {
+		self.max_buf_size = Some(max);
+		self
+	}
+
+	/// Sets whether half closed connections are supported.
+	pub fn half_close(mut self, half_close: bool) -> Self {
+		self.half_close = Some(half_close);
+		self
+	}
+
+	/// Sets whether header names in responses should be title cased.
+	pub fn title_case_headers(mut self, enabled: bool) -> Self {
+		self.title_case_headers = Some(enabled);
+		self
+	}
+
+	/// Sets whether the case of header names should be preserved.
+	pub fn preserve_header_case(mut self, enabled: bool) -> Self {
+		self.preserve_header_case = Some(enabled);
+		self
+	}
+
+	/// Returns the name of the first setting which was set.
+	fn first_set(&self) -> Option<&'static str> {
+		[
+			("keep_alive", self.keep_alive.is_some()),
+			("header_read_timeout", self.header_read_timeout.is_some()),
+			("max_headers", self.max_headers.is_some()),
+			("max_buf_size", self.max_buf_size.is_some()),
+			("half_close", self.half_close.is_some()),
+			("title_case_headers", self.title_case_headers.is_some()),
+			("preserve_header_case", self.preserve_header_case.is_some()),
+		]
+		.into_iter()
+		.find_map(|(name, set)| set.then_some(name))
+	}
+
+	#[cfg(feature = "http1")]
+	fn apply(&self, builder: &mut Builder<TokioExecutor>) {
+		let mut http1 = builder.http1();
+
+		if let Some(keep_alive) = self.keep_alive {
+			http1.keep_alive(keep_alive);
+		}
+		if let Some(timeout) = self.header_read_timeout {
+			http1.timer(TokioTimer::new());
+			http1.header_read_timeout(timeout);
+		}
+		if let Some(max) = self.max_headers {
+			http1.max_headers(max);
+		}
+		if let Some(max) = self.max_buf_size {
+			http1.max_buf_size(max);
+		}
+		if let Some(half_close) = self.half_close {
+			http1.half_close(half_close);
+		}
+		if let Some(enabled) = self.title_case_headers {
+			http1.title_case_headers(enabled);
+		}
+		if let Some(enabled) = self.preserve_header_case {
+			http1.preserve_header_case(enabled);
+		}
+	}
+}
+
+/// Settings for http/2 connections.
+///
+/// Requires the `http2` feature to be applied.
+#[derive(Debug, Clone, Default)]
+pub struct Http2Config {
+	max_concurrent_streams: Option<u32>,
+	initial_stream_window_size: Option<u32>,
+	initial_connection_window_size: Option<u32>,
+	adaptive_window: Option<bool>,
+	max_frame_size: Option<u32>,
+	max_header_list_size: Option<u32>,
+	max_send_buf_size: Option<usize>,
+	max_pending_accept_reset_streams: Option<usize>,
+	keep_alive_interval: Option<Duration>,
+	keep_alive_timeout: Option<Duration>,
+}
+
+impl Http2Config {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the maximum amount of concurrent streams per connection.
+	pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+		self.max_concurrent_streams = Some(max);
+		self
+	}
+
+	/// Sets the initial flow control window size of a stream.
+	pub fn initial_stream_window_size(mut self, size: u32) -> Self {
+		self.initial_stream_window_size = Some(size);
+		self
+	}
+
+	/// Sets the initial flow control window size of a connection.
+	pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+		self.initial_connection_window_size = Some(size);
+		self
+	}
+
+	/// Sets whether the window sizes should be adjusted based on the
+	/// bandwidth-delay product, this overrides the initial window sizes.
+	pub fn adaptive_window(mut self, enabled: bool) -> Self {
+		self.adaptive_window = Some(enabled);
+		self
+	}
+
+	/// Sets the maximum frame size.
+	pub fn max_frame_size(mut self, size: u32) -> Self {
+		self.max_frame_size = Some(size);
+		self
+	}
+
+	/// Sets the maximum size of the headers of a request.
+	pub fn max_header_list_size(mut self, max: u32) -> Self {
+		self.max_header_list_size = Some(max);
+		self
+	}
+
+	/// Sets the maximum size of the send buffer per stream.
+	pub fn max_send_buf_size(mut self, max: usize) -> Self {
+		self.max_send_buf_size = Some(max);
+		self
+	}
+
+	/// Sets the maximum amount of streams which were reset by the client
+	/// before they were accepted.
+	pub fn max_pending_accept_reset_streams(mut self, max: usize) -> Self {
+		self.max_pending_accept_reset_streams = Some(max);
+		self
+	}
+
+	/// Sets the interval in which ping frames are sent to keep the
+	/// connection alive.
+	pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+		self.keep_alive_interval = Some(interval);
+		self
+	}
+
+	/// Sets how long to wait for the response of a keep alive ping before
+	/// the connection gets closed.
+	///
+	/// Only has an effect if `keep_alive_interval` is set.
+	pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+		self.keep_alive_timeout = Some(timeout);
+		self
+	}
+
+	/// Returns the name of the first setting which was set.
+	fn first_set(&self) -> Option<&'static str> {
+		[
+			(
+				"max_concurrent_streams",
+				self.max_concurrent_streams.is_some(),
+			),
+			(
+				"initial_stream_window_size",
+				self.initial_stream_window_size.is_some(),
+			),
+			(
+				"initial_connection_window_size",
+				self.initial_connection_window_size.is_some(),
+			),
+			("adaptive_window", self.adaptive_window.is_some()),
+			("max_frame_size", self.max_frame_size.is_some()),
+			("max_header_list_size", self.max_header_list_size.is_some()),
+			("max_send_buf_size", self.max_send_buf_size.is_some()),
+			(
+				"max_pending_accept_reset_streams",
+				self.max_pending_accept_reset_streams.is_some(),
+			),
+			("keep_alive_interval", self.keep_alive_interval.is_some()),
+			("keep_alive_timeout", self.keep_alive_timeout.is_some()),
+		]
+		.into_iter()
+		.find_map(|(name, set)| set.then_some(name))
+	}
+
+	#[cfg(feature = "http2")]
+	fn apply(&self, builder: &mut Builder<TokioExecutor>) {
+		let mut http2 = builder.http2();
+
+		if let Some(max) = self.max_concurrent_streams {
+			http2.max_concurrent_streams(max);
+		}
+		if let Some(size) = self.initial_stream_window_size {
+			http2.initial_stream_window_size(size);
+		}
+		if let Some(size) = self.initial_connection_window_size {
+			http2.initial_connection_window_size(size);
+		}
+		if let Some(enabled) = self.adaptive_window {
+			http2.adaptive_window(enabled);
+		}
+		if let Some(size) = self.max_frame_size {
+			http2.max_frame_size(size);
+		}
+		if let Some(max) = self.max_header_list_size {
+			http2.max_header_list_size(max);
+		}
+		if let Some(max) = self.max_send_buf_size {
+			http2.max_send_buf_size(max);
+		}
+		if let Some(max) = self.max_pending_accept_reset_streams {
+			http2.max_pending_accept_reset_streams(max);
+		}
+		if let Some(interval) = self.keep_alive_interval {
+			// pings need a timer
+			http2.timer(TokioTimer::new());
+			http2.keep_alive_interval(interval);
+		}
+		if let Some(timeout) = self.keep_alive_timeout {
+			http2.keep_alive_timeout(timeout);
+		}
+	}
+}
+
+/// Http/1 and http/2 settings which are applied to every connection.
+#[derive(Debug, Clone, Default)]
+pub struct ProtocolConfig {
+	http1: Http1Config,
+	http2: Http2Config,
+}
+
+impl ProtocolConfig {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the http/1 settings.
+	pub fn http1(mut self, config: Http1Config) -> Self {
+		self.http1 = config;
+		self
+	}
+
+	/// Sets the http/2 settings.
+	pub fn http2(mut self, config: Http2Config) -> Self {
+		self.http2 = config;
+		self
+	}
+
+	/// Returns an error if a setting was set for a protocol whose feature is
+	/// not enabled.
+	pub fn validate(&self) -> Result<(), ProtocolError> {
+		if !cfg!(feature = "http1") {
+			if let Some(setting) = self.http1.first_set() {
+				return Err(ProtocolError::FeatureDisabled {
+					protocol: "http1",
+					setting,
+				});
+			}
+		}
+
+		if !cfg!(feature = "http2") {
+			if let Some(setting) = self.http2.first_set() {
+				return Err(ProtocolError::FeatureDisabled {
+					protocol: "http2",
+					setting,
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Creates the builder which serves every connection.
+	pub(crate) fn builder(&self) -> Builder<TokioExecutor> {
+		#[allow(unused_mut)]
+		let mut builder = Builder::new(TokioExecutor::new());
+
+		#[cfg(feature = "http1")]
+		self.http1.apply(&mut builder);
+		#[cfg(feature = "http2")]
+		self.http2.apply(&mut builder);
+
+		builder
+	}
+}
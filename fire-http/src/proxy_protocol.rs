@@ -0,0 +1,374 @@
+//! Support for the PROXY protocol of HAProxy.
+//!
+//! A load balancer which speaks the PROXY protocol sends the address of the
+//! client before any other data, so the server can see the real address
+//! instead of the address of the load balancer.
+//!
+//! ## Example
+//! ```no_run
+//! # use fire_http as fire;
+//! use fire::listener::Listener;
+//! use fire::proxy_protocol::ProxyProtocol;
+//!
+//! # async fn run() {
+//! let listener = Listener::bind("0.0.0.0:3000").await.unwrap();
+//! let listener = listener.with_proxy_protocol(ProxyProtocol::Required);
+//!
+//! let mut server = fire::build_with_listener(listener);
+//! # }
+//! ```
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Buf, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// How long a client can take to send the PROXY protocol header.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+// the longest possible v1 header including the crlf
+const V1_MAX_LEN: usize = 107;
+
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+
+/// Whether a listener expects a PROXY protocol header.
+///
+/// Version 1 (text) and version 2 (binary) headers are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyProtocol {
+	/// Every connection needs to start with a PROXY protocol header,
+	/// connections with a missing or malformed header are closed.
+	Required,
+	/// Connections can start with a PROXY protocol header, if none is
+	/// present the address of the connection is used.
+	///
+	/// ## Warning
+	/// Only use this if untrusted clients cannot reach the listener, since
+	/// they could send a header with any address.
+	Optional,
+}
+
+/// Reads the PROXY protocol header from the stream.
+///
+/// Returns the stream with every byte which was read but is not part of the
+/// header and the source address if one was sent.
+pub(crate) async fn read_header<S>(
+	mut stream: S,
+	mode: ProxyProtocol,
+) -> io::Result<(Rewind<S>, Option<SocketAddr>)>
+where
+	S: AsyncRead + Unpin,
+{
+	let mut buf = BytesMut::with_capacity(V1_MAX_LEN);
+
+	let read = async {
+		loop {
+			match parse(&buf)? {
+				Parsed::Incomplete => {}
+				Parsed::Header { len, source } => {
+					buf.advance(len);
+					return Ok(source);
+				}
+				Parsed::NotProxy if mode == ProxyProtocol::Optional => {
+					return Ok(None)
+				}
+				Parsed::NotProxy => {
+					return Err(invalid_data("missing proxy protocol header"))
+				}
+			}
+
+			if stream.read_buf(&mut buf).await? == 0 {
+				return Err(io::ErrorKind::UnexpectedEof.into());
+			}
+		}
+	};
+
+	let source = tokio::time::timeout(HEADER_TIMEOUT, read)
+		.await
+		.map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+
+	Ok((Rewind::new(stream, buf.freeze()), source))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Parsed {
+	/// More bytes are needed to decide.
+	Incomplete,
+	Header {
+		/// the length of the header in bytes
+		len: usize,
+		source: Option<SocketAddr>,
+	},
+	NotProxy,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn starts_with(buf: &[u8], prefix: &[u8]) -> bool {
+	let len = buf.len().min(prefix.len());
+	buf[..len] == prefix[..len]
+}
+
+fn parse(buf: &[u8]) -> io::Result<Parsed> {
+	if buf.is_empty() {
+		Ok(Parsed::Incomplete)
+	} else if starts_with(buf, V2_SIGNATURE) {
+		parse_v2(buf)
+	} else if starts_with(buf, V1_PREFIX) {
+		parse_v1(buf)
+	} else {
+		Ok(Parsed::NotProxy)
+	}
+}
+
+/// Parses a header like `PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n`.
+fn parse_v1(buf: &[u8]) -> io::Result<Parsed> {
+	let max = buf.len().min(V1_MAX_LEN);
+	let Some(end) = buf[..max].windows(2).position(|w| w == b"\r\n") else {
+		return if buf.len() >= V1_MAX_LEN {
+			Err(invalid_data("proxy protocol v1 header too long"))
+		} else {
+			Ok(Parsed::Incomplete)
+		};
+	};
+
+	let line = std::str::from_utf8(&buf[V1_PREFIX.len()..end])
+		.map_err(|_| invalid_data("proxy protocol v1 header not utf8"))?;
+	let mut parts = line.split(' ');
+
+	let source = match parts.next() {
+		Some("UNKNOWN") => None,
+		Some(proto @ ("TCP4" | "TCP6")) => {
+			let parts: Vec<_> = parts.collect();
+			let [src, _dst, src_port, _dst_port] = parts[..] else {
+				return Err(invalid_data("invalid proxy protocol v1 header"));
+			};
+
+			let ip: IpAddr = src
+				.parse()
+				.map_err(|_| invalid_data("invalid proxy protocol address"))?;
+			let port: u16 = src_port
+				.parse()
+				.map_err(|_| invalid_data("invalid proxy protocol port"))?;
+
+			if ip.is_ipv4() != (proto == "TCP4") {
+				return Err(invalid_data("proxy protocol address mismatch"));
+			}
+
+			Some(SocketAddr::new(ip, port))
+		}
+		_ => return Err(invalid_data("invalid proxy protocol v1 header")),
+	};
+
+	Ok(Parsed::Header {
+		len: end + 2,
+		source,
+	})
+}
+
+/// Parses the binary header of version 2.
+fn parse_v2(buf: &[u8]) -> io::Result<Parsed> {
+	if buf.len() < V2_HEADER_LEN {
+		return Ok(Parsed::Incomplete);
+	}
+
+	let version = buf[12] >> 4;
+	let command = buf[12] & 0x0f;
+	let family = buf[13] >> 4;
+	let addr_len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+
+	if version != 2 {
+		return Err(invalid_data("unsupported proxy protocol version"));
+	}
+
+	let len = V2_HEADER_LEN + addr_len;
+	if buf.len() < len {
+		return Ok(Parsed::Incomplete);
+	}
+
+	let addr = &buf[V2_HEADER_LEN..len];
+
+	let source = match (command, family) {
+		// LOCAL, the connection was opened by the proxy itself
+		(0x0, _) => None,
+		// PROXY over ipv4
+		(0x1, 0x1) if addr.len() >= 12 => {
+			let ip: [u8; 4] = addr[..4].try_into().unwrap();
+			let port = u16::from_be_bytes([addr[8], addr[9]]);
+			Some(SocketAddr::new(Ipv4Addr::from(ip).into(), port))
+		}
+		// PROXY over ipv6
+		(0x1, 0x2) if addr.len() >= 36 => {
+			let ip: [u8; 16] = addr[..16].try_into().unwrap();
+			let port = u16::from_be_bytes([addr[32], addr[33]]);
+			Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port))
+		}
+		// PROXY with an unspecified or unix address
+		(0x1, 0x0 | 0x3) => None,
+		_ => return Err(invalid_data("invalid proxy protocol v2 header")),
+	};
+
+	Ok(Parsed::Header { len, source })
+}
+
+/// A stream which first returns bytes which were already read.
+pub(crate) struct Rewind<S> {
+	prefix: Bytes,
+	inner: S,
+}
+
+impl<S> Rewind<S> {
+	pub fn new(inner: S, prefix: Bytes) -> Self {
+		Self { prefix, inner }
+	}
+}
+
+impl<S> AsyncRead for Rewind<S>
+where
+	S: AsyncRead + Unpin,
+{
+	fn poll_read(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &mut ReadBuf<'_>,
+	) -> Poll<io::Result<()>> {
+		let me = self.get_mut();
+
+		if !me.prefix.is_empty() {
+			let len = me.prefix.len().min(buf.remaining());
+			buf.put_slice(&me.prefix.split_to(len));
+			return Poll::Ready(Ok(()));
+		}
+
+		Pin::new(&mut me.inner).poll_read(cx, buf)
+	}
+}
+
+impl<S> AsyncWrite for Rewind<S>
+where
+	S: AsyncWrite + Unpin,
+{
+	fn poll_write(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		buf: &[u8],
+	) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+	) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		bufs: &[io::IoSlice<'_>],
+	) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		self.inner.is_write_vectored()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn v2(command: u8, family: u8, addr: &[u8]) -> Vec<u8> {
+		let mut buf = V2_SIGNATURE.to_vec();
+		buf.push(0x20 | command);
+		buf.push(family << 4 | 0x1);
+		buf.extend_from_slice(&(addr.len() as u16).to_be_bytes());
+		buf.extend_from_slice(addr);
+		buf
+	}
+
+	#[test]
+	fn v1() {
+		let header = b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\nGET";
+		assert_eq!(
+			parse(header).unwrap(),
+			Parsed::Header {
+				len: header.len() - 3,
+				source: Some("192.0.2.1:56324".parse().unwrap())
+			}
+		);
+
+		let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\n";
+		assert_eq!(
+			parse(header).unwrap(),
+			Parsed::Header {
+				len: header.len(),
+				source: Some("[2001:db8::1]:56324".parse().unwrap())
+			}
+		);
+
+		let header = b"PROXY UNKNOWN\r\n";
+		assert_eq!(
+			parse(header).unwrap(),
+			Parsed::Header {
+				len: header.len(),
+				source: None
+			}
+		);
+
+		assert_eq!(parse(b"PRO").unwrap(), Parsed::Incomplete);
+		assert_eq!(parse(b"PROXY TCP4 192.0").unwrap(), Parsed::Incomplete);
+		assert!(parse(b"PROXY TCP4 192.0.2.1 56324 443\r\n").is_err());
+		assert!(parse(b"PROXY TCP6 192.0.2.1 192.0.2.2 1 2\r\n").is_err());
+		assert!(parse(&[b'P'; 200]).is_ok_and(|p| p == Parsed::NotProxy));
+	}
+
+	#[test]
+	fn v2_header() {
+		let addr = [192, 0, 2, 1, 192, 0, 2, 2, 0xdc, 0x04, 0x01, 0xbb];
+		let header = v2(0x1, 0x1, &addr);
+		assert_eq!(
+			parse(&header).unwrap(),
+			Parsed::Header {
+				len: header.len(),
+				source: Some("192.0.2.1:56324".parse().unwrap())
+			}
+		);
+
+		let header = v2(0x0, 0x0, &[]);
+		assert_eq!(
+			parse(&header).unwrap(),
+			Parsed::Header {
+				len: header.len(),
+				source: None
+			}
+		);
+
+		let header = v2(0x1, 0x1, &addr);
+		assert_eq!(parse(&header[..20]).unwrap(), Parsed::Incomplete);
+		assert!(parse(&v2(0x1, 0x2, &addr)).is_err());
+	}
+
+	#[test]
+	fn not_proxy() {
+		assert_eq!(parse(b"GET / HTTP/1.1\r\n").unwrap(), Parsed::NotProxy);
+		assert_eq!(parse(b"").unwrap(), Parsed::Incomplete);
+	}
+}
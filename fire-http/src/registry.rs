@@ -0,0 +1,173 @@
+//! The routes and resources gathered by `FireBuilder` and `ReloadBuilder`.
+
+use crate::into::IntoRoute;
+use crate::resources::Resources;
+use crate::routes::{
+	Catcher, Middleware, ParamsNames, RawRoute, Route, RouteError, RouteErrors,
+	Routes, Scope,
+};
+
+use std::any::Any;
+
+/// Gathers routes and resources, used by `FireBuilder` and `ReloadBuilder`
+/// which dereference to it.
+pub struct Registry {
+	pub(crate) resources: Resources,
+	pub(crate) routes: Routes,
+}
+
+impl Registry {
+	pub(crate) fn new() -> Self {
+		Self {
+			resources: Resources::new(),
+			routes: Routes::new(),
+		}
+	}
+
+	/// Returns a reference to the current data.
+	pub fn data(&self) -> &Resources {
+		&self.resources
+	}
+
+	pub fn add_data<D>(&mut self, data: D)
+	where
+		D: Any + Send + Sync,
+	{
+		self.resources.insert(data);
+	}
+
+	/// Adds a `RawRoute`.
+	///
+	/// ## Panics
+	/// If the route conflicts with another one or its requirements are not
+	/// met, unless errors are collected, see `collect_route_errors`.
+	#[track_caller]
+	pub fn add_raw_route<R>(&mut self, route: R)
+	where
+		R: RawRoute + 'static,
+	{
+		let res = self.try_add_raw_route(route);
+		self.routes.report(res)
+	}
+
+	/// Adds a `RawRoute`, returning an error if it conflicts with another
+	/// route or its requirements are not met.
+	pub fn try_add_raw_route<R>(&mut self, route: R) -> Result<(), RouteError>
+	where
+		R: RawRoute + 'static,
+	{
+		let path = route.path();
+		self.routes.add_raw_to(None, path, route, &self.resources)
+	}
+
+	/// Adds a `Route`.
+	///
+	/// ## Panics
+	/// If the route conflicts with another one or its requirements are not
+	/// met, unless errors are collected, see `collect_route_errors`.
+	#[track_caller]
+	pub fn add_route<R>(&mut self, route: R)
+	where
+		R: IntoRoute + 'static,
+	{
+		let res = self.try_add_route(route);
+		self.routes.report(res)
+	}
+
+	/// Adds a `Route`, returning an error if it conflicts with another
+	/// route or its requirements are not met.
+	pub fn try_add_route<R>(&mut self, route: R) -> Result<(), RouteError>
+	where
+		R: IntoRoute + 'static,
+	{
+		let route = route.into_route();
+		let path = route.path();
+		self.routes.add_to(None, path, route, &self.resources)
+	}
+
+	/// Collects the errors of every route added afterwards instead of
+	/// panicking at the first one, including routes added in scopes.
+	///
+	/// The errors are returned by `check_routes`, errors which are left get
+	/// returned by `FireBuilder::build` and `ReloadHandle::reload`.
+	pub fn collect_route_errors(&mut self) {
+		self.routes.collect_errors()
+	}
+
+	/// Returns every error collected since `collect_route_errors` was
+	/// called.
+	pub fn check_routes(&mut self) -> Result<(), RouteErrors> {
+		let errors = self.routes.take_errors();
+		if errors.is_empty() {
+			Ok(())
+		} else {
+			Err(RouteErrors(errors))
+		}
+	}
+
+	/// Sets a route which handles every request no route exists for, not
+	/// even for another method, instead of responding with
+	/// `404 Not Found`. The method and path of the route are ignored.
+	///
+	/// Can be used to serve the index of a single page application.
+	pub fn fallback<R>(&mut self, route: R)
+	where
+		R: IntoRoute + 'static,
+	{
+		let route = route.into_route();
+		route.validate_requirements(&ParamsNames::parse(""), &self.resources);
+		self.routes.set_fallback(route)
+	}
+
+	/// Adds a `Catcher`.
+	pub fn add_catcher<C>(&mut self, catcher: C)
+	where
+		C: Catcher + 'static,
+	{
+		catcher.validate_data(&self.resources);
+		self.routes.push_catcher(catcher)
+	}
+
+	/// Adds a `Middleware` which runs for every request, in the order it was
+	/// added and before the middleware of scopes.
+	pub fn add_middleware<M>(&mut self, middleware: M)
+	where
+		M: Middleware + 'static,
+	{
+		middleware.validate_data(&self.resources);
+		self.routes.push_middleware(middleware)
+	}
+
+	/// Adds a group of routes which share a path prefix, catchers,
+	/// middleware, a request size limit and a request timeout. See `Scope`.
+	///
+	/// ## Panics
+	/// If the prefix does not start with a slash or contains a parameter.
+	#[track_caller]
+	pub fn scope<F>(&mut self, prefix: &str, f: F)
+	where
+		F: FnOnce(&mut Scope),
+	{
+		f(&mut Scope::new(&mut self.routes, &self.resources, prefix))
+	}
+
+	/// Adds a group of routes which only match requests to the host. See
+	/// `Scope`.
+	///
+	/// The host is either exact like `example.com` or contains parameters
+	/// which match a single label like `{tenant}.example.com`. Parameters
+	/// are available like path parameters.
+	///
+	/// Routes bound to an exact host are tried first, then routes bound to
+	/// a host with parameters and then routes which are not bound to a host.
+	///
+	/// ## Panics
+	/// If the host contains an empty label or an invalid parameter.
+	#[track_caller]
+	pub fn host<F>(&mut self, host: &str, f: F)
+	where
+		F: FnOnce(&mut Scope),
+	{
+		f(&mut Scope::host(&mut self.routes, &self.resources, host))
+	}
+}
@@ -0,0 +1,138 @@
+//! Swap the routes and resources of a running `Fire`.
+//!
+//! Requests which are already being handled, including open websockets,
+//! keep the routes and resources they started with. New requests use the
+//! swapped ones.
+//!
+//! ## Example
+//! ```
+//! # use fire_http as fire;
+//! use fire::get;
+//!
+//! #[get("/")]
+//! fn hello() -> &'static str { "Hello, World!" }
+//!
+//! # async fn run(fire: fire::Fire) {
+//! let handle = fire.reload_handle();
+//! tokio::task::spawn(fire.ignite());
+//!
+//! // later
+//! let mut builder = handle.builder();
+//! builder.add_route(hello);
+//! if let Err(e) = handle.reload(builder) {
+//!     eprintln!("reload failed, keeping the current routes: {e}");
+//! }
+//! # }
+//! ```
+
+use crate::fire::{Hearth, RequestConfigs, Wood};
+use crate::routes::{RouteErrors, TrailingSlash};
+use crate::Registry;
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Gathers new routes and resources which replace the current ones of a
+/// `Fire` with `ReloadHandle::reload`.
+pub struct ReloadBuilder {
+	registry: Registry,
+	configs: RequestConfigs,
+}
+
+impl ReloadBuilder {
+	fn new(configs: RequestConfigs) -> Self {
+		Self {
+			registry: Registry::new(),
+			configs,
+		}
+	}
+
+	/// Sets the request size limit. The default is the limit currently used.
+	///
+	/// ## Panics
+	/// If the size is zero.
+	pub fn request_size_limit(&mut self, size_limit: usize) {
+		self.configs.size_limit(size_limit)
+	}
+
+	/// Sets the request timeout. The default is the timeout currently used.
+	pub fn request_timeout(&mut self, timeout: Duration) {
+		self.configs.timeout(timeout)
+	}
+
+	/// Sets how requests are handled whose path only differs by a trailing
+	/// slash from a route. The default is the behavior currently used.
+	pub fn trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+		self.configs.trailing_slash(trailing_slash)
+	}
+}
+
+impl Deref for ReloadBuilder {
+	type Target = Registry;
+
+	fn deref(&self) -> &Registry {
+		&self.registry
+	}
+}
+
+impl DerefMut for ReloadBuilder {
+	fn deref_mut(&mut self) -> &mut Registry {
+		&mut self.registry
+	}
+}
+
+impl fmt::Debug for ReloadBuilder {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReloadBuilder")
+			.field("configs", &self.configs)
+			.finish_non_exhaustive()
+	}
+}
+
+/// A handle which can swap the routes and resources of a `Fire`, even
+/// while it is running.
+#[derive(Clone)]
+pub struct ReloadHandle {
+	hearth: Arc<Hearth>,
+}
+
+impl ReloadHandle {
+	pub(crate) fn new(hearth: Arc<Hearth>) -> Self {
+		Self { hearth }
+	}
+
+	/// Returns an empty builder which uses the current request size limit
+	/// and timeout.
+	pub fn builder(&self) -> ReloadBuilder {
+		ReloadBuilder::new(self.hearth.wood().configs().clone())
+	}
+
+	/// Replaces all routes, catchers and resources atomically.
+	///
+	/// Connection limits, trusted proxies and listeners are not affected.
+	///
+	/// If route errors were collected and not returned by `check_routes`
+	/// they are returned and the current routes are kept.
+	pub fn reload(
+		&self,
+		mut builder: ReloadBuilder,
+	) -> Result<(), RouteErrors> {
+		builder.check_routes()?;
+
+		self.hearth.swap(Wood::new(
+			builder.registry.resources,
+			builder.registry.routes,
+			builder.configs,
+		));
+
+		Ok(())
+	}
+}
+
+impl fmt::Debug for ReloadHandle {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReloadHandle").finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,141 @@
+use super::path_params::{without_constraints, TemplatePart};
+
+use std::str::FromStr;
+
+use regex::Regex;
+
+/// The constraints of the parameters of a template like `{id:u64}` or
+/// `{slug:[a-z-]+}`, which need to match for a route to be selected.
+///
+/// A constraint is either the name of a primitive type the value needs to
+/// parse into or a regular expression which needs to match the whole value.
+/// Values are checked without percent decoding, the same way `PathParam`
+/// parses them.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Constraints {
+	list: Vec<(String, Constraint)>,
+}
+
+#[derive(Debug, Clone)]
+enum Constraint {
+	Type(fn(&str) -> bool),
+	Regex(Regex),
+}
+
+fn parses<T: FromStr>(s: &str) -> bool {
+	s.parse::<T>().is_ok()
+}
+
+impl Constraint {
+	fn parse(s: &str) -> Result<Self, String> {
+		let parse = match s {
+			"u8" => parses::<u8>,
+			"u16" => parses::<u16>,
+			"u32" => parses::<u32>,
+			"u64" => parses::<u64>,
+			"u128" => parses::<u128>,
+			"usize" => parses::<usize>,
+			"i8" => parses::<i8>,
+			"i16" => parses::<i16>,
+			"i32" => parses::<i32>,
+			"i64" => parses::<i64>,
+			"i128" => parses::<i128>,
+			"isize" => parses::<isize>,
+			"f32" => parses::<f32>,
+			"f64" => parses::<f64>,
+			"bool" => parses::<bool>,
+			// a single word is most probably a typo of a type
+			s if template::is_word(s) => {
+				return Err(format!("unknown constraint {s:?}"));
+			}
+			s => {
+				return Regex::new(&format!("^(?:{s})$"))
+					.map(Self::Regex)
+					.map_err(|e| e.to_string());
+			}
+		};
+
+		Ok(Self::Type(parse))
+	}
+
+	fn matches(&self, value: &str) -> bool {
+		match self {
+			Self::Type(parses) => parses(value),
+			Self::Regex(regex) => regex.is_match(value),
+		}
+	}
+}
+
+impl Constraints {
+	/// Returns the template without constraints and the constraints.
+	pub fn parse(template: &str) -> Result<(String, Self), String> {
+		let mut list = vec![];
+
+		let parts = template::parse(template).map_err(|e| e.to_string())?;
+		for part in parts {
+			if let TemplatePart::Param {
+				name,
+				constraint: Some(constraint),
+				..
+			} = part
+			{
+				list.push((name.to_string(), Constraint::parse(constraint)?));
+			}
+		}
+
+		Ok((without_constraints(template), Self { list }))
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty()
+	}
+
+	/// Returns true if every parameter matches its constraint.
+	pub fn matches(&self, params: &[(&str, &str)]) -> bool {
+		self.list.iter().all(|(name, constraint)| {
+			params
+				.iter()
+				.find(|(n, _)| n == name)
+				.is_some_and(|(_, value)| constraint.matches(value))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn matches(template: &str, path: &str) -> bool {
+		let (stripped, constraints) = Constraints::parse(template).unwrap();
+		let mut router = matchit::Router::new();
+		router.insert(stripped, ()).unwrap();
+		router.at(path).is_ok_and(|mat| {
+			let params: Vec<_> = mat.params.iter().collect();
+			constraints.matches(&params)
+		})
+	}
+
+	#[test]
+	fn types() {
+		assert!(matches("/users/{id:u64}", "/users/42"));
+		assert!(!matches("/users/{id:u64}", "/users/abc"));
+		assert!(!matches("/users/{id:u8}", "/users/256"));
+		assert!(matches("/flags/{on:bool}", "/flags/true"));
+		assert!(!matches("/users/{id:u64}", "/users/%34%32"));
+	}
+
+	#[test]
+	fn regex() {
+		assert!(matches("/posts/{slug:[a-z-]+}", "/posts/hello-world"));
+		assert!(!matches("/posts/{slug:[a-z-]+}", "/posts/Hello"));
+		assert!(matches("/codes/{code:[0-9]{3}}", "/codes/404"));
+		assert!(!matches("/codes/{code:[0-9]{3}}", "/codes/4040"));
+		assert!(!matches("/posts/{slug:[a-z-]+}", "/posts/%61"));
+	}
+
+	#[test]
+	fn invalid() {
+		assert!(Constraints::parse("/users/{id:uuid}").is_err());
+		assert!(Constraints::parse("/users/{id:[a-z}").is_err());
+	}
+}
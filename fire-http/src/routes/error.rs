@@ -0,0 +1,54 @@
+use super::RouteInfo;
+
+use std::fmt;
+
+/// An error returned when a route could not be added.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum RouteError {
+	/// The route would never or only sometimes be selected because of
+	/// another route with the same method.
+	#[error("route {route} conflicts with {existing}")]
+	Conflict {
+		route: Box<RouteInfo>,
+		existing: Box<RouteInfo>,
+	},
+	/// Another route has the same name.
+	#[error("route {route} has the same name as {existing}")]
+	DuplicateName {
+		route: Box<RouteInfo>,
+		existing: Box<RouteInfo>,
+	},
+	/// The path template is not valid.
+	#[error("route {route} is invalid: {message}")]
+	InvalidPath {
+		route: Box<RouteInfo>,
+		message: String,
+	},
+	/// `try_validate_requirements` returned an error, for example because a
+	/// resource is missing.
+	///
+	/// Routes which only implement `validate_requirements` report a panic,
+	/// which is only caught if panics unwind.
+	#[error("requirements of route {route} are not met: {message}")]
+	Requirements {
+		route: Box<RouteInfo>,
+		message: String,
+	},
+}
+
+/// Every error collected after calling `collect_route_errors`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteErrors(pub Vec<RouteError>);
+
+impl fmt::Display for RouteErrors {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} route errors", self.0.len())?;
+		for error in &self.0 {
+			write!(f, "\n- {error}")?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for RouteErrors {}
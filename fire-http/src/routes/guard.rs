@@ -0,0 +1,296 @@
+//! Guards select between routes with the same method and path.
+//!
+//! Routes with the same method and path are tried in the order they were
+//! added, the first one whose guards all pass handles the request. If no
+//! route passes, the response is `415 Unsupported Media Type` if a content
+//! type guard failed, `406 Not Acceptable` if an accept guard failed or else
+//! `404 Not Found`.
+//!
+//! ## Example
+//! ```
+//! # use fire_http as fire;
+//! use fire::post;
+//! use fire::header::Mime;
+//! use fire::routes::{Guard, Guarded};
+//!
+//! #[post("/items")]
+//! fn create_json() -> &'static str { "json" }
+//!
+//! #[post("/items")]
+//! fn create_xml() -> &'static str { "xml" }
+//!
+//! # async fn run() {
+//! let mut fire = fire::build("0.0.0.0:3000").await.unwrap();
+//! let json = Guard::content_type(Mime::JSON);
+//! fire.add_route(Guarded::new(create_json).guard(json));
+//! let xml = Guard::content_type(Mime::XML);
+//! fire.add_route(Guarded::new(create_xml).guard(xml));
+//! # }
+//! ```
+
+use super::{ParamsNames, PathParams, Route, RouteKind, RoutePath};
+
+use crate::header::values::HeaderName;
+use crate::header::{Mime, RequestHeader, StatusCode, ACCEPT, CONTENT_TYPE};
+use crate::into::IntoRoute;
+use crate::util::PinnedFuture;
+use crate::{Request, Resources, Response};
+
+use std::fmt;
+use std::sync::Arc;
+
+type MatchFn = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A predicate over the request header which needs to pass for a route to
+/// be selected.
+#[derive(Clone)]
+pub struct Guard {
+	inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+	HeaderEquals(HeaderName, String),
+	HeaderMatches(HeaderName, MatchFn),
+	ContentType(Mime),
+	Accept(Mime),
+	Query(String),
+}
+
+impl Guard {
+	/// Passes if the header exists with exactly this value.
+	///
+	/// ## Panics
+	/// If the name is not a valid header name.
+	#[track_caller]
+	pub fn header(name: &str, value: impl Into<String>) -> Self {
+		Self {
+			inner: Inner::HeaderEquals(header_name(name), value.into()),
+		}
+	}
+
+	/// Passes if the header exists and the function returns true for its
+	/// value.
+	///
+	/// ## Panics
+	/// If the name is not a valid header name.
+	#[track_caller]
+	pub fn header_matches<F>(name: &str, f: F) -> Self
+	where
+		F: Fn(&str) -> bool + Send + Sync + 'static,
+	{
+		Self {
+			inner: Inner::HeaderMatches(header_name(name), Arc::new(f)),
+		}
+	}
+
+	/// Passes if the request has this content type, parameters like the
+	/// charset are ignored.
+	pub fn content_type(mime: Mime) -> Self {
+		Self {
+			inner: Inner::ContentType(mime),
+		}
+	}
+
+	/// Passes if the client accepts this content type or does not send an
+	/// `Accept` header.
+	pub fn accept(mime: Mime) -> Self {
+		Self {
+			inner: Inner::Accept(mime),
+		}
+	}
+
+	/// Passes if the query contains the key.
+	pub fn query(key: impl Into<String>) -> Self {
+		Self {
+			inner: Inner::Query(key.into()),
+		}
+	}
+
+	/// Returns the status code a request should get if this guard fails for
+	/// every route.
+	pub(crate) fn status_code(&self) -> StatusCode {
+		match self.inner {
+			Inner::ContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			Inner::Accept(_) => StatusCode::NOT_ACCEPTABLE,
+			_ => StatusCode::NOT_FOUND,
+		}
+	}
+
+	pub fn check(&self, header: &RequestHeader) -> bool {
+		match &self.inner {
+			Inner::HeaderEquals(name, value) => {
+				header.value(name).is_some_and(|v| v == value)
+			}
+			Inner::HeaderMatches(name, f) => {
+				header.value(name).is_some_and(|v| f(v))
+			}
+			Inner::ContentType(mime) => {
+				header.value(CONTENT_TYPE).is_some_and(|v| {
+					essence(v).eq_ignore_ascii_case(mime.as_str())
+				})
+			}
+			Inner::Accept(mime) => match header.value(ACCEPT) {
+				Some(accept) => accepts(accept, *mime),
+				None => true,
+			},
+			Inner::Query(key) => header
+				.uri()
+				.query()
+				.unwrap_or("")
+				.split('&')
+				.any(|pair| pair.split('=').next() == Some(key)),
+		}
+	}
+}
+
+impl fmt::Debug for Guard {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.inner {
+			Inner::HeaderEquals(name, value) => {
+				f.debug_tuple("Header").field(name).field(value).finish()
+			}
+			Inner::HeaderMatches(name, _) => {
+				f.debug_tuple("HeaderMatches").field(name).finish()
+			}
+			Inner::ContentType(mime) => {
+				f.debug_tuple("ContentType").field(mime).finish()
+			}
+			Inner::Accept(mime) => f.debug_tuple("Accept").field(mime).finish(),
+			Inner::Query(key) => f.debug_tuple("Query").field(key).finish(),
+		}
+	}
+}
+
+#[track_caller]
+fn header_name(name: &str) -> HeaderName {
+	HeaderName::from_bytes(name.as_bytes())
+		.unwrap_or_else(|_| panic!("invalid header name {name:?}"))
+}
+
+/// Returns the media type without parameters.
+fn essence(value: &str) -> &str {
+	value.split(';').next().unwrap_or("").trim()
+}
+
+/// Returns true if any media range of the accept header matches the mime
+/// type and does not have a quality of zero.
+fn accepts(accept: &str, mime: Mime) -> bool {
+	let (ty, subty) =
+		mime.as_str().split_once('/').unwrap_or((mime.as_str(), ""));
+
+	accept.split(',').any(|range| {
+		let mut parts = range.split(';');
+		let media = parts.next().unwrap_or("").trim();
+
+		let rejected = parts.any(|param| {
+			let param = param.trim();
+			param
+				.strip_prefix("q=")
+				.and_then(|q| q.parse::<f32>().ok())
+				.is_some_and(|q| q <= 0.0)
+		});
+		if rejected {
+			return false;
+		}
+
+		match media.split_once('/') {
+			Some(("*", "*")) => true,
+			Some((r_ty, "*")) => r_ty.eq_ignore_ascii_case(ty),
+			Some((r_ty, r_subty)) => {
+				r_ty.eq_ignore_ascii_case(ty)
+					&& r_subty.eq_ignore_ascii_case(subty)
+			}
+			None => false,
+		}
+	})
+}
+
+/// A route which is only selected if every guard passes.
+pub struct Guarded<R> {
+	route: R,
+	guards: Vec<Guard>,
+}
+
+impl<R> Guarded<R>
+where
+	R: Route,
+{
+	pub fn new<I>(route: I) -> Self
+	where
+		I: IntoRoute<IntoRoute = R>,
+	{
+		Self {
+			route: route.into_route(),
+			guards: vec![],
+		}
+	}
+
+	/// Adds a guard which needs to pass.
+	pub fn guard(mut self, guard: Guard) -> Self {
+		self.guards.push(guard);
+		self
+	}
+}
+
+impl<R> Route for Guarded<R>
+where
+	R: Route,
+{
+	fn validate_requirements(
+		&self,
+		params: &ParamsNames,
+		resources: &Resources,
+	) {
+		self.route.validate_requirements(params, resources)
+	}
+
+	fn try_validate_requirements(
+		&self,
+		params: &ParamsNames,
+		resources: &Resources,
+	) -> Result<(), String> {
+		self.route.try_validate_requirements(params, resources)
+	}
+
+	fn path(&self) -> RoutePath {
+		self.route.path()
+	}
+
+	fn kind(&self) -> RouteKind {
+		self.route.kind()
+	}
+
+	fn name(&self) -> Option<&str> {
+		self.route.name()
+	}
+
+	fn guards(&self) -> Vec<Guard> {
+		let mut guards = self.route.guards();
+		guards.extend(self.guards.iter().cloned());
+		guards
+	}
+
+	fn call<'a>(
+		&'a self,
+		req: &'a mut Request,
+		params: &'a PathParams,
+		resources: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<Response>> {
+		self.route.call(req, params, resources)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn accept() {
+		assert!(accepts("text/html, */*;q=0.8", Mime::JSON));
+		assert!(accepts("application/*", Mime::JSON));
+		assert!(accepts("Application/JSON;q=1", Mime::JSON));
+		assert!(!accepts("application/json;q=0", Mime::JSON));
+		assert!(!accepts("text/html", Mime::JSON));
+	}
+}
@@ -0,0 +1,123 @@
+/// A host which routes can be bound to, either exact like `example.com` or
+/// with parameters which match a single label like `{tenant}.example.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct HostPattern {
+	pattern: String,
+	labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Label {
+	Exact(String),
+	Param(String),
+}
+
+impl HostPattern {
+	/// ## Panics
+	/// If the pattern is empty or contains an empty label.
+	#[track_caller]
+	pub fn parse(pattern: &str) -> Self {
+		let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+
+		let labels = pattern
+			.split('.')
+			.map(|label| {
+				assert!(
+					!label.is_empty(),
+					"host {pattern:?} cannot contain empty labels"
+				);
+
+				match label.strip_prefix('{').and_then(|l| l.strip_suffix('}'))
+				{
+					Some(name) => {
+						assert!(
+							!name.is_empty() && !name.contains(['{', '}']),
+							"host {pattern:?} contains an invalid parameter"
+						);
+						Label::Param(name.to_string())
+					}
+					None => {
+						assert!(
+							!label.contains(['{', '}']),
+							"host parameters need to span a whole label in \
+							{pattern:?}"
+						);
+						Label::Exact(label.to_string())
+					}
+				}
+			})
+			.collect();
+
+		Self { pattern, labels }
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.pattern
+	}
+
+	/// Returns true if the pattern does not contain parameters.
+	pub fn is_exact(&self) -> bool {
+		self.labels.iter().all(|l| matches!(l, Label::Exact(_)))
+	}
+
+	/// Returns the captured parameters if the host matches. The host should
+	/// not contain a port.
+	pub fn matches<'a>(&self, host: &'a str) -> Option<Vec<(&str, &'a str)>> {
+		let host = host.trim_end_matches('.');
+		let mut params = vec![];
+
+		let mut host_labels = host.split('.');
+		for label in &self.labels {
+			let host_label = host_labels.next()?;
+
+			match label {
+				Label::Exact(exact) => {
+					if !exact.eq_ignore_ascii_case(host_label) {
+						return None;
+					}
+				}
+				Label::Param(_) if host_label.is_empty() => return None,
+				Label::Param(name) => params.push((name.as_str(), host_label)),
+			}
+		}
+
+		if host_labels.next().is_some() {
+			return None;
+		}
+
+		Some(params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn exact() {
+		let host = HostPattern::parse("Example.com");
+		assert!(host.is_exact());
+		assert_eq!(host.matches("example.COM."), Some(vec![]));
+		assert_eq!(host.matches("www.example.com"), None);
+		assert_eq!(host.matches("example.org"), None);
+	}
+
+	#[test]
+	fn params() {
+		let host = HostPattern::parse("{tenant}.example.com");
+		assert!(!host.is_exact());
+		assert_eq!(
+			host.matches("Acme.example.com"),
+			Some(vec![("tenant", "Acme")])
+		);
+		assert_eq!(host.matches("example.com"), None);
+		assert_eq!(host.matches("a.b.example.com"), None);
+		assert_eq!(host.matches(".example.com"), None);
+	}
+
+	#[test]
+	#[should_panic]
+	fn partial_label() {
+		HostPattern::parse("app-{tenant}.example.com");
+	}
+}
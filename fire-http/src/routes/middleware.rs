@@ -0,0 +1,52 @@
+use crate::util::PinnedFuture;
+use crate::{Request, Resources, Response};
+
+/// Code which runs around every route, for example to check authentication
+/// or to add headers.
+///
+/// Middleware runs in the order it was added, global middleware before the
+/// one of scopes and outer scopes before inner ones. `before` gets called
+/// before the route is looked up and can return a response which skips
+/// every following middleware and the route. Values a handler needs can be
+/// stored in `req.header.extensions` and read with the `Ext`
+/// extractor.
+///
+/// `after` gets called in the reverse order, only for middleware whose
+/// `before` was called, and after the catchers so it sees the final
+/// response.
+///
+/// For raw routes like websockets the request passed to the middleware has
+/// no body, the extensions are still available to their extractors. Raw
+/// routes are looked up before `before` gets called, so changing the method
+/// or the path of the request does not change which raw route is called.
+pub trait Middleware: Send + Sync {
+	// check if every data you expect is in Data
+	fn validate_data(&self, _data: &Resources) {}
+
+	/// Returns a response if the request should not reach the route.
+	///
+	/// The method and the path can be changed to reach another route, this
+	/// only works for routes which are not raw routes and only if the
+	/// original request did not match a raw route.
+	///
+	/// An error gets converted into a response with its status code.
+	fn before<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<Option<Response>>> {
+		PinnedFuture::new(async { Ok(None) })
+	}
+
+	/// Can modify or replace the response.
+	///
+	/// An error replaces the response with its status code.
+	fn after<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		_resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, crate::Result<()>> {
+		PinnedFuture::new(async { Ok(()) })
+	}
+}
@@ -2,37 +2,217 @@ mod raw_route;
 pub use raw_route::{HyperBody, HyperRequest, RawRoute};
 
 mod route;
-pub use route::{Route, RoutePath};
+pub use route::{Route, RouteInfo, RouteKind, RoutePath};
 
 mod router;
-use router::Router;
+use router::{method_to_num, Router};
+
+mod constraint;
+use constraint::Constraints;
+
+pub mod guard;
+pub use guard::{Guard, Guarded};
 
 mod catcher;
 pub use catcher::Catcher;
 
+mod middleware;
+pub use middleware::Middleware;
+
+mod host;
+use host::HostPattern;
+
+mod scope;
+use scope::Group;
+pub use scope::Scope;
+
+mod trailing_slash;
+pub use trailing_slash::TrailingSlash;
+
 mod path_params;
+use path_params::normalize;
 pub use path_params::{ParamsNames, PathParams};
 
-use crate::header::Method;
+mod url;
+pub use url::RouteUrl;
 
+mod error;
+pub use error::{RouteError, RouteErrors};
+
+use crate::fire::RequestConfigs;
+use crate::header::{Method, RequestHeader, StatusCode};
+
+use crate::Resources;
+
+use std::mem;
 use std::slice;
+use std::time::Duration;
 
 type BoxedRawRoute = Box<dyn RawRoute>;
 type BoxedRoute = Box<dyn Route>;
 type BoxedCatcher = Box<dyn Catcher>;
+type BoxedMiddleware = Box<dyn Middleware>;
 
-pub struct Routes {
+/// A route with the guards and limits it returned when it was added.
+struct Candidate {
+	route: BoxedRoute,
+	guards: Vec<Guard>,
+	size_limit: Option<usize>,
+	timeout: Option<Duration>,
+}
+
+impl Candidate {
+	/// Overrides the configs with the limits of the route.
+	fn apply_configs(&self, configs: &mut RequestConfigs) {
+		if let Some(size_limit) = self.size_limit {
+			configs.size_limit = size_limit;
+		}
+		if let Some(timeout) = self.timeout {
+			configs.timeout = timeout;
+		}
+	}
+
+	/// Returns the status code of the first guard which failed.
+	fn check(&self, header: &RequestHeader) -> Result<(), StatusCode> {
+		match self.guards.iter().find(|g| !g.check(header)) {
+			Some(guard) => Err(guard.status_code()),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Keeps the status code for a request which was rejected by guards,
+/// preferring `415` over `406` over `404`.
+#[derive(Default)]
+struct Rejection(Option<StatusCode>);
+
+impl Rejection {
+	fn add(&mut self, status_code: StatusCode) {
+		let rank = |s: &StatusCode| match *s {
+			StatusCode::UNSUPPORTED_MEDIA_TYPE => 2,
+			StatusCode::NOT_ACCEPTABLE => 1,
+			_ => 0,
+		};
+
+		if self
+			.0
+			.as_ref()
+			.map_or(true, |s| rank(s) < rank(&status_code))
+		{
+			self.0 = Some(status_code);
+		}
+	}
+}
+
+/// The routes registered for a single host or for every host.
+struct Table {
 	raw: Router<BoxedRawRoute>,
-	basic: Router<BoxedRoute>,
+	basic: Router<Candidate>,
+}
+
+impl Table {
+	fn new() -> Self {
+		Self {
+			raw: Router::new(),
+			basic: Router::new(),
+		}
+	}
+
+	fn route_raw<'a, 'b>(
+		&'a self,
+		method: &Method,
+		path: &'b str,
+	) -> Option<(&'a BoxedRawRoute, Vec<(&'a str, &'b str)>)> {
+		let (routes, params) = self
+			.raw
+			// first try with the correct method
+			.at(Some(method), path)
+			.or_else(|| self.raw.at(None, path))?;
+
+		// raw routes only share a path if they have constraints
+		let route = routes.iter().find(|r| r.matches(&params))?;
+		Some((&route.value, route.params(&params)))
+	}
+
+	fn route<'a, 'b>(
+		&'a self,
+		header: &RequestHeader,
+		path: &'b str,
+		rejection: &mut Rejection,
+	) -> Option<(&'a Candidate, Vec<(&'a str, &'b str)>)> {
+		let method = header.method();
+		let methods = [
+			// first try with the correct method
+			Some(Some(method)),
+			// head requests are answered by get routes if no head route
+			// exists, the body gets removed later
+			(*method == Method::HEAD).then_some(Some(&Method::GET)),
+			// then routes matching any method
+			Some(None),
+		];
+
+		for method in methods.into_iter().flatten() {
+			let Some((candidates, params)) = self.basic.at(method, path) else {
+				continue;
+			};
+
+			for entry in candidates.iter().filter(|c| c.matches(&params)) {
+				let candidate = &entry.value;
+				match candidate.check(header) {
+					Ok(()) => return Some((candidate, entry.params(&params))),
+					Err(status_code) => rejection.add(status_code),
+				}
+			}
+		}
+
+		None
+	}
+}
+
+pub struct Routes {
+	table: Table,
+	hosts: Vec<(HostPattern, Table)>,
 	catcher: Vec<BoxedCatcher>,
+	middleware: Vec<BoxedMiddleware>,
+	fallback: Option<BoxedRoute>,
+	groups: Vec<Group>,
+	infos: Vec<RouteInfo>,
+	errors: Option<Vec<RouteError>>,
 }
 
 impl Routes {
 	pub fn new() -> Self {
 		Self {
-			raw: Router::new(),
-			basic: Router::new(),
+			table: Table::new(),
+			hosts: vec![],
 			catcher: vec![],
+			middleware: vec![],
+			fallback: None,
+			groups: vec![],
+			infos: vec![],
+			errors: None,
+		}
+	}
+
+	/// Returns the index of the host, adding it if it does not exist yet.
+	fn host_index(&mut self, host: HostPattern) -> usize {
+		match self.hosts.iter().position(|(h, _)| *h == host) {
+			Some(idx) => idx,
+			None => {
+				self.hosts.push((host, Table::new()));
+				self.hosts.len() - 1
+			}
+		}
+	}
+
+	fn host(&self, host: Option<usize>) -> Option<&HostPattern> {
+		host.map(|idx| &self.hosts[idx].0)
+	}
+
+	fn table_mut(&mut self, host: Option<usize>) -> &mut Table {
+		match host {
+			Some(idx) => &mut self.hosts[idx].1,
+			None => &mut self.table,
 		}
 	}
 
@@ -41,9 +221,62 @@ impl Routes {
 	where
 		R: RawRoute + 'static,
 	{
-		self.raw
-			.insert(path.method.as_ref(), path.path, Box::new(route))
-			.unwrap();
+		let res = self.insert_raw_to(None, path, route);
+		self.report(res)
+	}
+
+	/// Validates the requirements of the route and adds it.
+	pub(crate) fn add_raw_to<R>(
+		&mut self,
+		host: Option<usize>,
+		path: RoutePath,
+		route: R,
+		resources: &Resources,
+	) -> Result<(), RouteError>
+	where
+		R: RawRoute + 'static,
+	{
+		let info = self.info::<R>(host, &path, route.kind(), true);
+		self.validate(host, &path, &info, |names| {
+			route.try_validate_requirements(names, resources)
+		})?;
+		self.insert_raw_to(host, path, route)
+	}
+
+	fn insert_raw_to<R>(
+		&mut self,
+		host: Option<usize>,
+		path: RoutePath,
+		route: R,
+	) -> Result<(), RouteError>
+	where
+		R: RawRoute + 'static,
+	{
+		let info = self
+			.info::<R>(host, &path, route.kind(), true)
+			.with_name(route.name());
+		self.check_name(&info)?;
+
+		let (template, constraints) = self.constraints(&info)?;
+
+		let table = self.table_mut(host);
+		// a route without constraints would always be selected
+		let unconstrained = table
+			.raw
+			.get(path.method.as_ref(), &template)
+			.iter()
+			.any(|r| r.constraints.is_empty());
+		if unconstrained {
+			return Err(self.conflict(info, &template));
+		}
+
+		let res = table.raw.insert(
+			path.method.as_ref(),
+			&template,
+			constraints,
+			Box::new(route),
+		);
+		self.finish_insert(info, res)
 	}
 
 	#[track_caller]
@@ -51,9 +284,202 @@ impl Routes {
 	where
 		R: Route + 'static,
 	{
-		self.basic
-			.insert(path.method.as_ref(), path.path, Box::new(route))
-			.unwrap();
+		let res = self.insert_to(None, path, route);
+		self.report(res)
+	}
+
+	/// Validates the requirements of the route and adds it.
+	pub(crate) fn add_to<R>(
+		&mut self,
+		host: Option<usize>,
+		path: RoutePath,
+		route: R,
+		resources: &Resources,
+	) -> Result<(), RouteError>
+	where
+		R: Route + 'static,
+	{
+		let info = self.info::<R>(host, &path, route.kind(), false);
+		self.validate(host, &path, &info, |names| {
+			route.try_validate_requirements(names, resources)
+		})?;
+		self.insert_to(host, path, route)
+	}
+
+	fn insert_to<R>(
+		&mut self,
+		host: Option<usize>,
+		path: RoutePath,
+		route: R,
+	) -> Result<(), RouteError>
+	where
+		R: Route + 'static,
+	{
+		let size_limit = route.size_limit();
+		let timeout = route.timeout();
+		let info = self
+			.info::<R>(host, &path, route.kind(), false)
+			.with_name(route.name())
+			.with_limits(size_limit, timeout);
+		self.check_name(&info)?;
+
+		let (template, constraints) = self.constraints(&info)?;
+
+		let table = self.table_mut(host);
+		// a route without guards and constraints would always be selected
+		// before the new one
+		let unguarded = table
+			.basic
+			.get(path.method.as_ref(), &template)
+			.iter()
+			.any(|c| c.value.guards.is_empty() && c.constraints.is_empty());
+		if unguarded {
+			return Err(self.conflict(info, &template));
+		}
+
+		let candidate = Candidate {
+			guards: route.guards(),
+			route: Box::new(route),
+			size_limit,
+			timeout,
+		};
+		let res = table.basic.insert(
+			path.method.as_ref(),
+			&template,
+			constraints,
+			candidate,
+		);
+		self.finish_insert(info, res)
+	}
+
+	fn info<R>(
+		&self,
+		host: Option<usize>,
+		path: &RoutePath,
+		kind: RouteKind,
+		raw: bool,
+	) -> RouteInfo {
+		let handler = std::any::type_name::<R>();
+		RouteInfo::new(path, self.host(host), kind, handler, raw)
+	}
+
+	/// Calls `f` with the parameter names of the path and the host, an error
+	/// gets returned as `RouteError::Requirements`.
+	///
+	/// Routes only catch panics in `validate_requirements` if panics unwind,
+	/// see `Route::try_validate_requirements`.
+	fn validate(
+		&self,
+		host: Option<usize>,
+		path: &RoutePath,
+		info: &RouteInfo,
+		f: impl FnOnce(&ParamsNames) -> Result<(), String>,
+	) -> Result<(), RouteError> {
+		let invalid = |message| RouteError::InvalidPath {
+			route: Box::new(info.clone()),
+			message,
+		};
+		let mut names = ParamsNames::try_parse(&path.path).map_err(invalid)?;
+		if let Some(host) = self.host(host) {
+			names.extend(
+				ParamsNames::try_parse(host.as_str()).map_err(invalid)?,
+			);
+		}
+
+		f(&names).map_err(|message| RouteError::Requirements {
+			route: Box::new(info.clone()),
+			message,
+		})
+	}
+
+	fn check_name(&self, info: &RouteInfo) -> Result<(), RouteError> {
+		let Some(name) = &info.name else {
+			return Ok(());
+		};
+
+		match self.infos.iter().find(|i| i.name.as_ref() == Some(name)) {
+			Some(existing) => Err(RouteError::DuplicateName {
+				route: Box::new(info.clone()),
+				existing: Box::new(existing.clone()),
+			}),
+			None => Ok(()),
+		}
+	}
+
+	/// Returns the template without constraints and the constraints.
+	fn constraints(
+		&self,
+		info: &RouteInfo,
+	) -> Result<(String, Constraints), RouteError> {
+		Constraints::parse(&info.path).map_err(|message| {
+			RouteError::InvalidPath {
+				route: Box::new(info.clone()),
+				message,
+			}
+		})
+	}
+
+	/// Returns a conflict with the last route added with the template.
+	fn conflict(&self, info: RouteInfo, template: &str) -> RouteError {
+		let template = normalize(template).0;
+		let existing =
+			self.infos.iter().rev().find(|i| {
+				i.same_tree(&info) && normalize(&i.path).0 == template
+			});
+
+		match existing {
+			Some(existing) => RouteError::Conflict {
+				route: Box::new(info),
+				existing: Box::new(existing.clone()),
+			},
+			None => RouteError::InvalidPath {
+				message: format!("conflicts with {template}"),
+				route: Box::new(info),
+			},
+		}
+	}
+
+	fn finish_insert(
+		&mut self,
+		info: RouteInfo,
+		res: Result<(), matchit::InsertError>,
+	) -> Result<(), RouteError> {
+		match res {
+			Ok(()) => {
+				self.infos.push(info);
+				Ok(())
+			}
+			Err(matchit::InsertError::Conflict { with }) => {
+				Err(self.conflict(info, &with))
+			}
+			Err(e) => Err(RouteError::InvalidPath {
+				route: Box::new(info),
+				message: e.to_string(),
+			}),
+		}
+	}
+
+	/// Starts collecting errors instead of panicking, see `take_errors`.
+	pub fn collect_errors(&mut self) {
+		self.errors.get_or_insert_with(Vec::new);
+	}
+
+	/// Returns the errors collected since `collect_errors` was called.
+	pub fn take_errors(&mut self) -> Vec<RouteError> {
+		self.errors.as_mut().map(mem::take).unwrap_or_default()
+	}
+
+	/// Collects the error or panics if errors are not collected.
+	#[track_caller]
+	pub(crate) fn report(&mut self, res: Result<(), RouteError>) {
+		let Err(e) = res else {
+			return;
+		};
+
+		match &mut self.errors {
+			Some(errors) => errors.push(e),
+			None => panic!("{e}"),
+		}
 	}
 
 	pub fn push_catcher<C>(&mut self, catcher: C)
@@ -63,37 +489,250 @@ impl Routes {
 		self.catcher.push(Box::new(catcher))
 	}
 
+	pub fn push_middleware<M>(&mut self, middleware: M)
+	where
+		M: Middleware + 'static,
+	{
+		self.middleware.push(Box::new(middleware))
+	}
+
+	pub fn set_fallback<R>(&mut self, route: R)
+	where
+		R: Route + 'static,
+	{
+		self.fallback = Some(Box::new(route));
+	}
+
+	pub fn fallback(&self) -> Option<&BoxedRoute> {
+		self.fallback.as_ref()
+	}
+
+	/// Returns the tables matching the host with their captured parameters,
+	/// exact hosts first, then hosts with parameters and then the routes
+	/// which match every host.
+	fn tables<'a, 'b>(
+		&'a self,
+		host: Option<&'b str>,
+	) -> impl Iterator<Item = (&'a Table, Vec<(&'a str, &'b str)>)> {
+		let hosts = host.into_iter().flat_map(move |host| {
+			let exact = self.hosts.iter().filter(|(h, _)| h.is_exact());
+			let params = self.hosts.iter().filter(|(h, _)| !h.is_exact());
+
+			exact.chain(params).filter_map(move |(pattern, table)| {
+				pattern.matches(host).map(|params| (table, params))
+			})
+		});
+
+		hosts.chain([(&self.table, vec![])])
+	}
+
 	pub fn route_raw<'a>(
 		&'a self,
 		method: &Method,
+		host: Option<&str>,
 		path: &str,
 	) -> Option<(&'a BoxedRawRoute, PathParams)> {
-		let (route, params) = self
-			.raw
-			// first try with the correct method
-			.at(Some(method), path)
-			.or_else(|| self.raw.at(None, path))?;
-
-		Some((route, PathParams::new(params)))
+		self.tables(host).find_map(|(table, host_params)| {
+			let (route, params) = table.route_raw(method, path)?;
+			Some((route, PathParams::with_host(params, host_params)))
+		})
 	}
 
+	/// Returns the first route matching the request whose guards pass.
+	///
+	/// Returns an error with the status code the request should get if
+	/// routes matched but their guards failed.
 	pub fn route<'a>(
 		&'a self,
-		method: &Method,
+		header: &RequestHeader,
+	) -> Option<Result<(&'a BoxedRoute, PathParams), StatusCode>> {
+		self.candidate(header).map(|res| {
+			res.map(|(candidate, params)| (&candidate.route, params))
+		})
+	}
+
+	fn candidate<'a>(
+		&'a self,
+		header: &RequestHeader,
+	) -> Option<Result<(&'a Candidate, PathParams), StatusCode>> {
+		let uri = header.uri();
+		let mut rejection = Rejection::default();
+
+		let found = self.tables(uri.host()).find_map(|(table, host_params)| {
+			let (route, params) =
+				table.route(header, uri.path(), &mut rejection)?;
+			Some((route, PathParams::with_host(params, host_params)))
+		});
+
+		match (found, rejection.0) {
+			(Some(found), _) => Some(Ok(found)),
+			(None, Some(status_code)) => Some(Err(status_code)),
+			(None, None) => None,
+		}
+	}
+
+	/// Returns every method for which a route matching the host and path
+	/// exists.
+	///
+	/// If any route exists, `HEAD` is included if a get route exists and
+	/// `OPTIONS` is always included since they are answered automatically.
+	///
+	/// Routes which match any method are not considered.
+	pub fn allowed_methods(
+		&self,
+		host: Option<&str>,
 		path: &str,
-	) -> Option<(&'a BoxedRoute, PathParams)> {
-		let (route, params) = self
-			.basic
-			// first try with the correct method
-			.at(Some(method), path)
-			.or_else(|| self.basic.at(None, path))?;
+	) -> Vec<Method> {
+		let mut methods: Vec<Method> = vec![];
+		let mut has_get = false;
+
+		for (table, _) in self.tables(host) {
+			for method in table
+				.raw
+				.methods_at(path)
+				.chain(table.basic.methods_at(path))
+			{
+				if !methods.contains(method) {
+					methods.push(method.clone());
+				}
+			}
+
+			has_get |= table.basic.methods_at(path).any(|m| m == Method::GET);
+		}
+
+		if methods.is_empty() {
+			return methods;
+		}
+
+		if has_get && !methods.contains(&Method::HEAD) {
+			methods.push(Method::HEAD);
+		}
+
+		if !methods.contains(&Method::OPTIONS) {
+			methods.push(Method::OPTIONS);
+		}
+
+		methods.sort_by(|a, b| {
+			(method_to_num(a), a.as_str()).cmp(&(method_to_num(b), b.as_str()))
+		});
+
+		methods
+	}
+
+	/// Returns true if a route matches the path exactly, a path with a
+	/// trailing slash only matches routes which end with a slash.
+	fn matches_exactly(&self, host: Option<&str>, path: &str) -> bool {
+		let slash = path.ends_with('/');
+
+		self.tables(host).any(|(table, _)| {
+			table
+				.raw
+				.templates_at(path)
+				.chain(table.basic.templates_at(path))
+				.any(|template| !slash || template.ends_with('/'))
+		})
+	}
+
+	/// Returns the path with a trailing slash added or removed if only that
+	/// path matches a route exactly.
+	pub fn trailing_slash_alternative(
+		&self,
+		host: Option<&str>,
+		path: &str,
+	) -> Option<String> {
+		if self.matches_exactly(host, path) {
+			return None;
+		}
+
+		trailing_slash::toggle(path)
+			.filter(|alt| self.matches_exactly(host, alt))
+	}
 
-		Some((route, PathParams::new(params)))
+	/// Returns every registered route in the order they were added.
+	pub fn infos(&self) -> &[RouteInfo] {
+		&self.infos
 	}
 
 	pub fn catchers(&self) -> slice::Iter<'_, BoxedCatcher> {
 		self.catcher.iter()
 	}
+
+	pub fn middleware(&self) -> slice::Iter<'_, BoxedMiddleware> {
+		self.middleware.iter()
+	}
+
+	/// Returns the groups matching the host and path, the innermost first.
+	///
+	/// Groups bound to a host are inside groups with the same prefix which
+	/// are not.
+	fn groups_at<'a>(
+		&'a self,
+		host: Option<&str>,
+		path: &str,
+	) -> Vec<&'a Group> {
+		let mut groups: Vec<_> = self
+			.groups
+			.iter()
+			.filter(|g| g.matches(path))
+			.filter(|g| match (self.host(g.host()), host) {
+				(None, _) => true,
+				(Some(pattern), Some(host)) => pattern.matches(host).is_some(),
+				(Some(_), None) => false,
+			})
+			.collect();
+		groups.sort_by_key(|g| {
+			std::cmp::Reverse((g.prefix_len(), g.host().is_some()))
+		});
+		groups
+	}
+
+	/// Returns the request configs for the request, with the overrides of
+	/// every matching scope and of the matching route applied.
+	pub(crate) fn request_configs(
+		&self,
+		header: &RequestHeader,
+		configs: &RequestConfigs,
+	) -> RequestConfigs {
+		let uri = header.uri();
+		let mut configs = configs.clone();
+		// outer scopes first so inner ones override them
+		for group in self.groups_at(uri.host(), uri.path()).into_iter().rev() {
+			group.apply_configs(&mut configs);
+		}
+		if let Some(Ok((candidate, _))) = self.candidate(header) {
+			candidate.apply_configs(&mut configs);
+		}
+		configs
+	}
+
+	/// Returns the catchers of every scope matching the host and path, the
+	/// innermost first, followed by the global catchers.
+	pub fn catchers_at<'a>(
+		&'a self,
+		host: Option<&str>,
+		path: &str,
+	) -> impl Iterator<Item = &'a BoxedCatcher> {
+		self.groups_at(host, path)
+			.into_iter()
+			.flat_map(|g| g.catchers())
+			.chain(self.catcher.iter())
+	}
+
+	/// Returns the middleware in the order it should be called, the global
+	/// middleware first, followed by the middleware of every scope matching
+	/// the host and path, the outermost first.
+	pub fn middleware_at<'a>(
+		&'a self,
+		host: Option<&str>,
+		path: &str,
+	) -> Vec<&'a dyn Middleware> {
+		let groups = self.groups_at(host, path).into_iter().rev();
+		self.middleware
+			.iter()
+			.chain(groups.flat_map(|g| g.middleware()))
+			.map(|m| &**m)
+			.collect()
+	}
 }
 
 impl Default for Routes {
@@ -1,10 +1,8 @@
-use std::{
-	collections::{HashMap, HashSet},
-	str::FromStr,
-};
+use std::borrow::Cow;
+use std::str::Utf8Error;
+use std::{collections::HashMap, str::FromStr};
 
-use byte_parser::{ParseIterator, StrParser};
-use matchit::Params;
+use percent_encoding::percent_decode_str;
 
 #[derive(Debug, Clone)]
 pub struct PathParams {
@@ -12,16 +10,35 @@ pub struct PathParams {
 }
 
 impl PathParams {
-	pub(crate) fn new(params: Params) -> Self {
+	pub(crate) fn new(params: Vec<(&str, &str)>) -> Self {
 		let mut inner = HashMap::new();
 
-		for (key, value) in params.iter() {
+		for (key, value) in params {
 			inner.insert(key.to_string(), value.to_string());
 		}
 
 		Self { inner }
 	}
 
+	pub(crate) fn empty() -> Self {
+		Self {
+			inner: HashMap::new(),
+		}
+	}
+
+	/// Creates the params from the path with the parameters captured from
+	/// the host.
+	pub(crate) fn with_host(
+		params: Vec<(&str, &str)>,
+		host: Vec<(&str, &str)>,
+	) -> Self {
+		let mut this = Self::new(params);
+		for (key, value) in host {
+			this.inner.insert(key.to_string(), value.to_string());
+		}
+		this
+	}
+
 	pub fn exists(&self, key: impl AsRef<str>) -> bool {
 		self.inner.contains_key(key.as_ref())
 	}
@@ -36,77 +53,110 @@ impl PathParams {
 	pub fn get(&self, key: impl AsRef<str>) -> Option<&str> {
 		self.inner.get(key.as_ref()).map(|s| s.as_str())
 	}
+
+	/// Returns the percent decoded value, `a%20b` becomes `a b`.
+	pub fn get_decoded(
+		&self,
+		key: impl AsRef<str>,
+	) -> Option<Result<Cow<'_, str>, Utf8Error>> {
+		self.get(key)
+			.map(|value| percent_decode_str(value).decode_utf8())
+	}
+}
+
+pub(crate) use template::TemplatePart;
+
+/// Parses the template into text and parameters.
+///
+/// ## Panics
+/// If the template is invalid.
+#[track_caller]
+pub(crate) fn parse_template(s: &str) -> Vec<TemplatePart<'_>> {
+	template::parse(s).unwrap_or_else(|e| panic!("{e}"))
 }
 
-/* pub struct Parser {
-	pub path: &'a str
-} */
+/// Returns the template with the constraints of the parameters removed.
+pub(crate) fn without_constraints(template: &str) -> String {
+	parse_template(template)
+		.into_iter()
+		.map(|part| match part {
+			TemplatePart::Text(text) => text.to_string(),
+			TemplatePart::Param {
+				name,
+				catch_all: true,
+				..
+			} => format!("{{*{name}}}"),
+			TemplatePart::Param { name, .. } => format!("{{{name}}}"),
+		})
+		.collect()
+}
 
-// we need to parse {}
-// and allow it to be escaped with {{ or }}
+/// Returns the template with the parameters renamed by their position and
+/// without constraints, together with the original names.
+///
+/// `/users/{id:u64}` and `/users/{name}` both become `/users/{p0}`, which
+/// allows them to share a path.
+pub(crate) fn normalize(template: &str) -> (String, Vec<String>) {
+	let mut names = vec![];
+	let normalized = parse_template(template)
+		.into_iter()
+		.map(|part| match part {
+			TemplatePart::Text(text) => text.to_string(),
+			TemplatePart::Param {
+				name, catch_all, ..
+			} => {
+				let star = if catch_all { "*" } else { "" };
+				let param = format!("{{{star}p{}}}", names.len());
+				names.push(name.to_string());
+				param
+			}
+		})
+		.collect();
+
+	(normalized, names)
+}
 
 #[derive(Debug, Clone)]
 pub struct ParamsNames<'a> {
-	list: HashSet<&'a str>,
+	// in the order they appear in the path
+	list: Vec<&'a str>,
 }
 
 impl<'a> ParamsNames<'a> {
+	#[track_caller]
 	pub fn parse(s: &'a str) -> Self {
-		let mut parser = StrParser::new(s);
-
-		let mut list = HashSet::new();
-
-		#[allow(clippy::never_loop)]
-		'template_loop: loop {
-			parser.consume_while_byte_fn(|&b| b != b'{');
-			// either we're at the end or we found a {
-			// we need to check for escapes
-			let Some(b) = parser.next() else {
-				return Self { list };
-			};
-			debug_assert_eq!(b, b'{');
-
-			// handle escapes
-			if parser.next_if(|&b| b == b'{').is_some() {
-				continue 'template_loop;
-			}
+		Self::try_parse(s).unwrap_or_else(|e| panic!("{e}"))
+	}
 
-			let mut parser = parser.record();
-
-			loop {
-				parser.consume_while_byte_fn(|&b| b != b'}' && b != b'{');
-				match parser.peek() {
-					Some(b'{') => {
-						panic!("unexpected {{");
-					}
-					Some(b'}') => {
-						assert!(
-							!matches!(parser.peek_at(2), Some(b) if b == b'}'),
-							"escapping does not work in template string"
-						);
-
-						let s = parser.to_str();
-						let s = s.trim_start_matches('*');
-						list.insert(s);
-
-						parser.next().unwrap();
-
-						continue 'template_loop;
-					}
-					Some(b) => unreachable!("reached byte {b}"),
-					None => {
-						panic!("unexpected end of string");
-					}
-				}
-			}
-		}
+	/// Returns an error if the template is invalid.
+	pub(crate) fn try_parse(s: &'a str) -> Result<Self, String> {
+		let list = template::param_names(s).map_err(|e| e.to_string())?;
+
+		Ok(Self { list })
 	}
 
 	pub fn exists(&self, key: impl AsRef<str>) -> bool {
-		self.list.contains(key.as_ref())
+		self.list.contains(&key.as_ref())
 	}
 
 	pub fn is_empty(&self) -> bool {
 		self.list.is_empty()
 	}
+
+	/// Adds the names of another template, for example of the host.
+	///
+	/// ## Panics
+	/// If a name exists in both.
+	#[track_caller]
+	pub(crate) fn extend(&mut self, other: ParamsNames<'a>) {
+		for name in other.list {
+			assert!(!self.exists(name), "parameter {name:?} exists twice");
+			self.list.push(name);
+		}
+	}
+
+	/// Returns the names in the order they appear in the path.
+	pub fn iter(&self) -> impl Iterator<Item = &'a str> + '_ {
+		self.list.iter().copied()
+	}
 }
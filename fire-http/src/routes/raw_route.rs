@@ -5,7 +5,8 @@ use crate::{Resources, Response};
 
 pub use crate::server::{HyperBody, HyperRequest};
 
-use super::{ParamsNames, PathParams, RoutePath};
+use super::route::catch_requirements;
+use super::{ParamsNames, PathParams, RouteKind, RoutePath};
 
 /// A `RawRoute` is the more powerfull brother/sister to `Route`. It get's
 /// executed before `Route`.
@@ -15,9 +16,30 @@ pub trait RawRoute: Send + Sync {
 	// check if every data you expect is in Data
 	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {}
 
+	/// Checks the requirements like `validate_requirements` but returns an
+	/// error message instead of panicking, see
+	/// `Route::try_validate_requirements`.
+	fn try_validate_requirements(
+		&self,
+		params: &ParamsNames,
+		data: &Resources,
+	) -> Result<(), String> {
+		catch_requirements(|| self.validate_requirements(params, data))
+	}
+
 	// get's only called once
 	fn path(&self) -> RoutePath;
 
+	/// Returns what kind of route this is, used by `FirePit::routes`.
+	fn kind(&self) -> RouteKind {
+		RouteKind::Raw
+	}
+
+	/// Returns the name of the route, see `Route::name`.
+	fn name(&self) -> Option<&str> {
+		None
+	}
+
 	fn call<'a>(
 		&'a self,
 		req: &'a mut HyperRequest,
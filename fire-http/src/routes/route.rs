@@ -1,17 +1,137 @@
-use super::{ParamsNames, PathParams};
+use super::{Guard, HostPattern, ParamsNames, PathParams};
 
 use crate::header::Method;
 use crate::util::PinnedFuture;
 use crate::{Request, Resources, Response};
 
 use std::borrow::Cow;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct RoutePath {
 	pub method: Option<Method>,
+	/// The path template, parameters are written as `{name}` or `{*rest}`
+	/// for the rest of the path and can have a constraint like `{id:u64}`
+	/// or `{slug:[a-z-]+}`.
+	///
+	/// Routes whose constraints fail are skipped, parameters at the same
+	/// position can have different names.
 	pub path: Cow<'static, str>,
 }
 
+/// What kind of handler a route is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RouteKind {
+	/// A `Route` which is not one of the other kinds.
+	Basic,
+	/// A `RawRoute` which is not one of the other kinds.
+	Raw,
+	/// Serves files from the filesystem or from memory.
+	StaticFiles,
+	/// A route created with the `api` macro.
+	Api,
+	/// A route created with the `ws` macro.
+	WebSocket,
+	/// A stream server.
+	Stream,
+}
+
+/// Describes a registered route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RouteInfo {
+	/// None if the route matches any method.
+	pub method: Option<Method>,
+	/// The host pattern if the route is bound to a host.
+	pub host: Option<String>,
+	/// The path template with the prefix of the scope.
+	pub path: String,
+	/// The names of the parameters in the host and the path, in the order
+	/// they appear.
+	pub params: Vec<String>,
+	pub kind: RouteKind,
+	/// The name given to the route, see `Route::name`.
+	pub name: Option<String>,
+	/// The type name of the handler.
+	pub handler: &'static str,
+	/// The request size limit of the route, see `Route::size_limit`.
+	pub size_limit: Option<usize>,
+	/// The request timeout of the route, see `Route::timeout`.
+	pub timeout: Option<Duration>,
+	raw: bool,
+}
+
+impl RouteInfo {
+	pub(super) fn new(
+		path: &RoutePath,
+		host: Option<&HostPattern>,
+		kind: RouteKind,
+		handler: &'static str,
+		raw: bool,
+	) -> Self {
+		let host_names = host.map(|h| ParamsNames::parse(h.as_str()));
+		// an invalid path gets reported as `RouteError::InvalidPath`
+		let path_names = ParamsNames::try_parse(&path.path).ok();
+
+		Self {
+			method: path.method.clone(),
+			host: host.map(|h| h.as_str().to_string()),
+			params: host_names
+				.iter()
+				.chain(path_names.iter())
+				.flat_map(|names| names.iter())
+				.map(String::from)
+				.collect(),
+			path: path.path.to_string(),
+			kind,
+			name: None,
+			handler,
+			size_limit: None,
+			timeout: None,
+			raw,
+		}
+	}
+
+	/// Returns true if both routes are in the same tree, raw routes and
+	/// routes are matched separately.
+	pub(super) fn same_tree(&self, other: &Self) -> bool {
+		self.raw == other.raw
+			&& self.method == other.method
+			&& self.host == other.host
+	}
+
+	pub(super) fn with_name(mut self, name: Option<&str>) -> Self {
+		self.name = name.map(String::from);
+		self
+	}
+
+	pub(super) fn with_limits(
+		mut self,
+		size_limit: Option<usize>,
+		timeout: Option<Duration>,
+	) -> Self {
+		self.size_limit = size_limit;
+		self.timeout = timeout;
+		self
+	}
+}
+
+impl fmt::Display for RouteInfo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.method {
+			Some(method) => write!(f, "{method} ")?,
+			None => f.write_str("* ")?,
+		}
+		if let Some(host) = &self.host {
+			f.write_str(host)?;
+		}
+		write!(f, "{} ({})", self.path, self.handler)
+	}
+}
+
 /// A `Route` is sort of a request handler
 ///
 /// If possible you should use the provided macros which implement Route for
@@ -24,9 +144,61 @@ pub trait Route: Send + Sync {
 		resources: &Resources,
 	);
 
+	/// Checks the requirements like `validate_requirements` but returns an
+	/// error message instead of panicking.
+	///
+	/// The default calls `validate_requirements` and returns its panic
+	/// message, which only works if panics unwind. The route macros
+	/// implement this, implement it too if the requirements should also be
+	/// reported with `panic = "abort"`.
+	fn try_validate_requirements(
+		&self,
+		params: &ParamsNames,
+		resources: &Resources,
+	) -> Result<(), String> {
+		catch_requirements(|| self.validate_requirements(params, resources))
+	}
+
 	// get's only called once
 	fn path(&self) -> RoutePath;
 
+	/// Returns what kind of route this is, used by `FirePit::routes`.
+	fn kind(&self) -> RouteKind {
+		RouteKind::Basic
+	}
+
+	/// Returns the name of the route, set with `name = "..."` in the route
+	/// macros. Names need to be unique.
+	fn name(&self) -> Option<&str> {
+		None
+	}
+
+	/// Returns the guards which need to pass for this route to be selected,
+	/// see `Guarded`.
+	///
+	/// get's only called once
+	fn guards(&self) -> Vec<Guard> {
+		vec![]
+	}
+
+	/// Returns the request size limit of this route, set with
+	/// `size_limit = ...` in the route macros. It overrides the limit of
+	/// the scopes and applies before any middleware is called.
+	///
+	/// get's only called once
+	fn size_limit(&self) -> Option<usize> {
+		None
+	}
+
+	/// Returns the request timeout of this route, set with `timeout = ...`
+	/// in the route macros. It overrides the timeout of the scopes and
+	/// applies before any middleware is called.
+	///
+	/// get's only called once
+	fn timeout(&self) -> Option<Duration> {
+		None
+	}
+
 	fn call<'a>(
 		&'a self,
 		req: &'a mut Request,
@@ -34,3 +206,13 @@ pub trait Route: Send + Sync {
 		resources: &'a Resources,
 	) -> PinnedFuture<'a, crate::Result<Response>>;
 }
+
+/// Calls `validate_requirements` returning the panic message as an error.
+pub(crate) fn catch_requirements(f: impl FnOnce()) -> Result<(), String> {
+	panic::catch_unwind(AssertUnwindSafe(f)).map_err(|e| {
+		e.downcast_ref::<&str>()
+			.map(|s| s.to_string())
+			.or_else(|| e.downcast_ref::<String>().cloned())
+			.unwrap_or_else(|| "validate_requirements panicked".into())
+	})
+}
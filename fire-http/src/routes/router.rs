@@ -1,59 +1,235 @@
+use super::constraint::Constraints;
+use super::path_params::normalize;
+
 use crate::header::Method;
 
-fn method_to_num(method: Option<&Method>) -> usize {
-	match method {
-		Some(&Method::GET) => 0,
-		Some(&Method::POST) => 1,
-		Some(&Method::PUT) => 2,
-		Some(&Method::DELETE) => 3,
-		Some(&Method::HEAD) => 4,
-		Some(&Method::OPTIONS) => 5,
-		Some(&Method::CONNECT) => 6,
-		Some(&Method::PATCH) => 7,
-		Some(&Method::TRACE) => 8,
-		_ => 9,
+use std::collections::HashMap;
+
+/// Methods which always have their own tree, in the order of
+/// `method_to_num`.
+const METHODS: [Method; 9] = [
+	Method::GET,
+	Method::POST,
+	Method::PUT,
+	Method::DELETE,
+	Method::HEAD,
+	Method::OPTIONS,
+	Method::CONNECT,
+	Method::PATCH,
+	Method::TRACE,
+];
+
+/// The tree of routes which match any method.
+const ANY: usize = METHODS.len();
+
+/// Returns the position of a method when sorting, extension methods come
+/// after the standard ones.
+pub(super) fn method_to_num(method: &Method) -> usize {
+	METHODS
+		.iter()
+		.position(|m| m == method)
+		.unwrap_or(METHODS.len())
+}
+
+/// A value with the constraints and parameter names of its template.
+pub struct Entry<T> {
+	pub value: T,
+	pub constraints: Constraints,
+	// the names of the parameters in the order they appear in the template
+	names: Vec<String>,
+}
+
+impl<T> Entry<T> {
+	/// Returns the parameters with the names of this entry.
+	pub fn params<'a, 'b>(
+		&'a self,
+		params: &matchit::Params<'_, 'b>,
+	) -> Vec<(&'a str, &'b str)> {
+		self.names
+			.iter()
+			.map(String::as_str)
+			.zip(params.iter().map(|(_, value)| value))
+			.collect()
+	}
+
+	pub fn matches(&self, params: &matchit::Params) -> bool {
+		self.constraints.matches(&self.params(params))
 	}
 }
 
+/// Stores every value registered for a method and path template, values
+/// with the same method and template are kept in the order they were added.
+///
+/// Templates are stored without their constraints and with the parameters
+/// named by their position, so `/users/{id:u64}` and `/users/{name}` share
+/// the template `/users/{p0}`.
+///
+/// matchit only returns the most specific template matching a path, if no
+/// constraints of its values match, the other templates of the same tree are
+/// tried in the order they were registered. So with `/users/{id:u64}` and
+/// `/{*rest}` the path `/users/abc` matches `/{*rest}`.
 pub struct Router<T> {
-	inner: Box<[matchit::Router<T>]>,
+	// the trees of METHODS, then the any tree and then the trees of the
+	// extension methods
+	inner: Vec<matchit::Router<usize>>,
+	// the templates of every tree in the order they were registered
+	indices: Vec<Vec<usize>>,
+	extensions: Vec<Method>,
+	values: Vec<Vec<Entry<T>>>,
+	// the template of every entry in values
+	paths: Vec<String>,
+	// a router containing only the template of every entry in values
+	singles: Vec<matchit::Router<usize>>,
+	templates: HashMap<(usize, String), usize>,
 }
 
 impl<T> Router<T> {
 	pub fn new() -> Self {
 		Self {
-			inner: (0..=9).map(|_| matchit::Router::new()).collect(),
+			inner: (0..=ANY).map(|_| matchit::Router::new()).collect(),
+			indices: vec![vec![]; ANY + 1],
+			extensions: vec![],
+			values: vec![],
+			paths: vec![],
+			singles: vec![],
+			templates: HashMap::new(),
 		}
 	}
 
+	/// Returns the index of the tree for the method if it exists.
+	fn tree(&self, method: Option<&Method>) -> Option<usize> {
+		let Some(method) = method else {
+			return Some(ANY);
+		};
+
+		METHODS.iter().position(|m| m == method).or_else(|| {
+			self.extensions
+				.iter()
+				.position(|m| m == method)
+				.map(|i| ANY + 1 + i)
+		})
+	}
+
+	fn tree_or_insert(&mut self, method: Option<&Method>) -> usize {
+		if let Some(num) = self.tree(method) {
+			return num;
+		}
+
+		// only extension methods don't have a tree yet
+		self.extensions.push(method.unwrap().clone());
+		self.inner.push(matchit::Router::new());
+		self.indices.push(vec![]);
+		self.inner.len() - 1
+	}
+
+	/// Adds the value after every value with the same method and template,
+	/// the template needs to be without constraints.
 	pub fn insert(
 		&mut self,
 		method: Option<&Method>,
-		path: impl Into<String>,
+		path: &str,
+		constraints: Constraints,
 		value: T,
 	) -> Result<(), matchit::InsertError> {
-		let num = method_to_num(method);
-		self.inner[num].insert(path, value)
+		let num = self.tree_or_insert(method);
+		let (path, names) = normalize(path);
+		let entry = Entry {
+			value,
+			constraints,
+			names,
+		};
+
+		if let Some(&idx) = self.templates.get(&(num, path.clone())) {
+			self.values[idx].push(entry);
+			return Ok(());
+		}
+
+		let idx = self.values.len();
+		self.inner[num].insert(path.clone(), idx)?;
+		let mut single = matchit::Router::new();
+		single.insert(path.clone(), idx)?;
+		self.indices[num].push(idx);
+		self.values.push(vec![entry]);
+		self.paths.push(path.clone());
+		self.singles.push(single);
+		self.templates.insert((num, path), idx);
+
+		Ok(())
 	}
 
+	/// Returns the values registered with exactly this method and template.
+	pub fn get(&self, method: Option<&Method>, path: &str) -> &[Entry<T>] {
+		let Some(num) = self.tree(method) else {
+			return &[];
+		};
+
+		self.templates
+			.get(&(num, normalize(path).0))
+			.map(|&idx| self.values[idx].as_slice())
+			.unwrap_or(&[])
+	}
+
+	/// Returns the values of the first template matching the path which
+	/// contains a value whose constraints match.
 	pub fn at<'a, 'b>(
 		&'a self,
 		method: Option<&Method>,
 		path: &'b str,
-	) -> Option<(&'a T, matchit::Params<'a, 'b>)> {
-		let num = method_to_num(method);
+	) -> Option<(&'a [Entry<T>], matchit::Params<'a, 'b>)> {
+		let num = self.tree(method)?;
+
+		self.match_at(num, path)
+			.map(|(idx, params)| (self.values[idx].as_slice(), params))
+	}
+
+	fn match_at<'a, 'b>(
+		&'a self,
+		num: usize,
+		path: &'b str,
+	) -> Option<(usize, matchit::Params<'a, 'b>)> {
+		let matches = |idx: usize, params: &matchit::Params| {
+			self.values[idx].iter().any(|e| e.matches(params))
+		};
+
+		let mat = self.inner[num].at(path).ok()?;
+		let first = *mat.value;
+		if matches(first, &mat.params) {
+			return Some((first, mat.params));
+		}
 
-		self.inner[num]
-			.at(path)
-			.map(|mat| (mat.value, mat.params))
-			.ok()
+		// matchit does not backtrack, so try the other templates
+		self.indices[num]
+			.iter()
+			.filter(|&&idx| idx != first)
+			.find_map(|&idx| {
+				let mat = self.singles[idx].at(path).ok()?;
+				matches(idx, &mat.params).then_some((idx, mat.params))
+			})
 	}
 
-	/* pub fn get(&self, method: &Method) -> &T {
-		&self.inner[*method as usize]
+	/// Returns the templates of every method which match the path.
+	pub fn templates_at<'a>(
+		&'a self,
+		path: &'a str,
+	) -> impl Iterator<Item = &'a str> + 'a {
+		(0..self.inner.len())
+			.filter_map(move |num| self.match_at(num, path))
+			.map(|(idx, _)| self.paths[idx].as_str())
 	}
 
-	pub fn get_mut(&mut self, method: &Method) -> &mut T {
-		&mut self.inner[*method as usize]
-	} */
+	/// Returns every method which has a value registered matching the path.
+	///
+	/// Values which are registered for any method are not considered.
+	pub fn methods_at<'a>(
+		&'a self,
+		path: &'a str,
+	) -> impl Iterator<Item = &'a Method> + 'a {
+		let methods = METHODS.iter().chain(self.extensions.iter());
+		let trees = (0..ANY).chain(ANY + 1..self.inner.len());
+
+		methods
+			.zip(trees)
+			.filter(move |&(_, num)| self.match_at(num, path).is_some())
+			.map(|(method, _)| method)
+	}
 }
@@ -0,0 +1,300 @@
+use super::{
+	BoxedCatcher, BoxedMiddleware, Catcher, HostPattern, Middleware, RawRoute,
+	Route, RouteError, Routes,
+};
+
+use crate::fire::RequestConfigs;
+use crate::into::IntoRoute;
+use crate::Resources;
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Settings which apply to every request whose path starts with the prefix
+/// of a group and which matches the host of the group if it has one.
+pub(super) struct Group {
+	host: Option<usize>,
+	prefix: String,
+	catchers: Vec<BoxedCatcher>,
+	middleware: Vec<BoxedMiddleware>,
+	size_limit: Option<usize>,
+	timeout: Option<Duration>,
+}
+
+impl Group {
+	fn new(host: Option<usize>, prefix: String) -> Self {
+		Self {
+			host,
+			prefix,
+			catchers: vec![],
+			middleware: vec![],
+			size_limit: None,
+			timeout: None,
+		}
+	}
+
+	pub fn matches(&self, path: &str) -> bool {
+		path.strip_prefix(&self.prefix)
+			.is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+	}
+
+	pub fn host(&self) -> Option<usize> {
+		self.host
+	}
+
+	pub fn prefix_len(&self) -> usize {
+		self.prefix.len()
+	}
+
+	pub fn catchers(&self) -> &[BoxedCatcher] {
+		&self.catchers
+	}
+
+	pub fn middleware(&self) -> &[BoxedMiddleware] {
+		&self.middleware
+	}
+
+	/// Overrides the values which were set in this group.
+	pub fn apply_configs(&self, configs: &mut RequestConfigs) {
+		if let Some(size_limit) = self.size_limit {
+			configs.size_limit = size_limit;
+		}
+		if let Some(timeout) = self.timeout {
+			configs.timeout = timeout;
+		}
+	}
+}
+
+/// A group of routes which share a path prefix, catchers, middleware, a
+/// request size limit and a request timeout, optionally bound to a host.
+///
+/// The catchers, middleware and limits apply to every request whose path
+/// starts with the prefix, even if no route matches. Nested scopes override
+/// the limits of their parents and their catchers get called first. Catchers
+/// of a scope are called before global catchers, while global middleware
+/// runs before the middleware of a scope.
+///
+/// ## Example
+/// ```
+/// # use fire_http as fire;
+/// use fire::get;
+///
+/// // the path gets prefixed with /api/v1
+/// #[get("/users")]
+/// fn users() -> &'static str { "[]" }
+///
+/// # async fn run() {
+/// let mut fire = fire::build("0.0.0.0:3000").await.unwrap();
+/// fire.scope("/api/v1", |scope| scope.add_route(users));
+/// // scopes with the same prefix share their settings
+/// fire.scope("/api/v1", |scope| scope.request_size_limit(1024 * 1024));
+/// # }
+/// ```
+pub struct Scope<'a> {
+	routes: &'a mut Routes,
+	resources: &'a Resources,
+	group: usize,
+}
+
+impl<'a> Scope<'a> {
+	/// ## Panics
+	/// If the prefix does not start with a slash or contains a parameter.
+	#[track_caller]
+	pub(crate) fn new(
+		routes: &'a mut Routes,
+		resources: &'a Resources,
+		prefix: &str,
+	) -> Self {
+		validate_prefix(prefix);
+		Self::with_group(routes, resources, None, prefix)
+	}
+
+	/// ## Panics
+	/// If the host contains an empty label or an invalid parameter.
+	#[track_caller]
+	pub(crate) fn host(
+		routes: &'a mut Routes,
+		resources: &'a Resources,
+		host: &str,
+	) -> Self {
+		let host = routes.host_index(HostPattern::parse(host));
+		Self::with_group(routes, resources, Some(host), "")
+	}
+
+	fn with_group(
+		routes: &'a mut Routes,
+		resources: &'a Resources,
+		host: Option<usize>,
+		prefix: &str,
+	) -> Self {
+		let prefix = prefix.trim_end_matches('/');
+
+		// scopes with the same host and prefix share their settings
+		let group = routes
+			.groups
+			.iter()
+			.position(|g| g.host == host && g.prefix == prefix);
+		let group = match group {
+			Some(group) => group,
+			None => {
+				routes.groups.push(Group::new(host, prefix.to_string()));
+				routes.groups.len() - 1
+			}
+		};
+
+		Self {
+			routes,
+			resources,
+			group,
+		}
+	}
+
+	fn group(&mut self) -> &mut Group {
+		&mut self.routes.groups[self.group]
+	}
+
+	/// Returns the host pattern every route is bound to.
+	pub fn host_pattern(&self) -> Option<&str> {
+		self.routes.host(self.host_idx()).map(HostPattern::as_str)
+	}
+
+	fn host_idx(&self) -> Option<usize> {
+		self.routes.groups[self.group].host
+	}
+
+	/// Returns the prefix which gets added to every route.
+	pub fn prefix(&self) -> &str {
+		&self.routes.groups[self.group].prefix
+	}
+
+	fn prefixed(&self, path: &str) -> String {
+		match path {
+			"" | "/" if !self.prefix().is_empty() => self.prefix().to_string(),
+			path => format!("{}{path}", self.prefix()),
+		}
+	}
+
+	/// Returns a reference to the current data.
+	pub fn data(&self) -> &Resources {
+		self.resources
+	}
+
+	/// Adds a `RawRoute` with the prefix of this scope.
+	///
+	/// ## Panics
+	/// If the route conflicts with another one or its requirements are not
+	/// met, unless errors are collected.
+	#[track_caller]
+	pub fn add_raw_route<R>(&mut self, route: R)
+	where
+		R: RawRoute + 'static,
+	{
+		let res = self.try_add_raw_route(route);
+		self.routes.report(res)
+	}
+
+	/// Adds a `RawRoute` with the prefix of this scope, returning an error if
+	/// it conflicts with another route or its requirements are not met.
+	pub fn try_add_raw_route<R>(&mut self, route: R) -> Result<(), RouteError>
+	where
+		R: RawRoute + 'static,
+	{
+		let mut path = route.path();
+		path.path = Cow::Owned(self.prefixed(&path.path));
+		let host = self.host_idx();
+		self.routes.add_raw_to(host, path, route, self.resources)
+	}
+
+	/// Adds a `Route` with the prefix of this scope.
+	///
+	/// ## Panics
+	/// If the route conflicts with another one or its requirements are not
+	/// met, unless errors are collected.
+	#[track_caller]
+	pub fn add_route<R>(&mut self, route: R)
+	where
+		R: IntoRoute + 'static,
+	{
+		let res = self.try_add_route(route);
+		self.routes.report(res)
+	}
+
+	/// Adds a `Route` with the prefix of this scope, returning an error if it
+	/// conflicts with another route or its requirements are not met.
+	pub fn try_add_route<R>(&mut self, route: R) -> Result<(), RouteError>
+	where
+		R: IntoRoute + 'static,
+	{
+		let route = route.into_route();
+		let mut path = route.path();
+		path.path = Cow::Owned(self.prefixed(&path.path));
+		let host = self.host_idx();
+		self.routes.add_to(host, path, route, self.resources)
+	}
+
+	/// Adds a `Catcher` which only gets called for requests in this scope.
+	pub fn add_catcher<C>(&mut self, catcher: C)
+	where
+		C: Catcher + 'static,
+	{
+		catcher.validate_data(self.resources);
+		self.group().catchers.push(Box::new(catcher))
+	}
+
+	/// Adds a `Middleware` which only runs for requests in this scope, after
+	/// the global middleware and the middleware of outer scopes.
+	pub fn add_middleware<M>(&mut self, middleware: M)
+	where
+		M: Middleware + 'static,
+	{
+		middleware.validate_data(self.resources);
+		self.group().middleware.push(Box::new(middleware))
+	}
+
+	/// Sets the request size limit for this scope.
+	///
+	/// ## Panics
+	/// If the size is zero.
+	pub fn request_size_limit(&mut self, size_limit: usize) {
+		assert!(size_limit > 0, "size limit needs to be bigger than zero");
+		self.group().size_limit = Some(size_limit);
+	}
+
+	/// Sets the request timeout for this scope.
+	pub fn request_timeout(&mut self, timeout: Duration) {
+		self.group().timeout = Some(timeout);
+	}
+
+	/// Creates a nested scope, the prefix gets appended to the prefix of
+	/// this scope and it is bound to the same host.
+	///
+	/// ## Panics
+	/// If the prefix does not start with a slash or contains a parameter.
+	#[track_caller]
+	pub fn scope<F>(&mut self, prefix: &str, f: F)
+	where
+		F: FnOnce(&mut Scope),
+	{
+		validate_prefix(prefix);
+		let prefix = format!("{}{prefix}", self.prefix());
+		let host = self.host_idx();
+		f(&mut Scope::with_group(
+			self.routes,
+			self.resources,
+			host,
+			&prefix,
+		))
+	}
+}
+
+#[track_caller]
+fn validate_prefix(prefix: &str) {
+	assert!(
+		prefix.starts_with('/'),
+		"scope prefix {prefix:?} needs to start with a slash"
+	);
+	assert!(
+		!prefix.contains(['{', '}']),
+		"scope prefix {prefix:?} cannot contain parameters"
+	);
+}
@@ -0,0 +1,49 @@
+use crate::header::StatusCode;
+
+/// How requests are handled whose path only differs by a trailing slash
+/// from the path of a route.
+///
+/// Only applies if no route matches the path of the request exactly. A
+/// path without a trailing slash matches if any route matches, a path with a
+/// trailing slash only if a route was registered with a trailing slash, so
+/// `/files/{*rem}` does not match `/files/docs/`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingSlash {
+	/// `/users` and `/users/` are different paths.
+	#[default]
+	Strict,
+	/// Redirects to the path of the route with `301 Moved Permanently`.
+	MovedPermanently,
+	/// Redirects to the path of the route with `308 Permanent Redirect`,
+	/// clients keep the method and the body.
+	PermanentRedirect,
+	/// Handles the request as if it was sent to the path of the route.
+	Transparent,
+}
+
+impl TrailingSlash {
+	/// Returns the status code of a redirect.
+	pub(crate) fn redirect(&self) -> Option<StatusCode> {
+		match self {
+			Self::MovedPermanently => Some(StatusCode::MOVED_PERMANENTLY),
+			Self::PermanentRedirect => Some(StatusCode::PERMANENT_REDIRECT),
+			Self::Strict | Self::Transparent => None,
+		}
+	}
+}
+
+/// Returns the path with a trailing slash added or removed.
+///
+/// Paths starting with `//` are ignored, since a redirect to them would be
+/// followed by browsers as a redirect to another host.
+pub(super) fn toggle(path: &str) -> Option<String> {
+	if path.starts_with("//") || path.starts_with("/\\") {
+		return None;
+	}
+
+	match path.strip_suffix('/') {
+		Some("") => None,
+		Some(path) => Some(path.to_string()),
+		None => Some(format!("{path}/")),
+	}
+}
@@ -0,0 +1,146 @@
+use super::path_params::{parse_template, TemplatePart};
+
+use std::fmt;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Every character except the unreserved ones gets encoded.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'-')
+	.remove(b'.')
+	.remove(b'_')
+	.remove(b'~');
+
+/// The url of a route, created by the `url` function the route macros
+/// generate. Parameters with a type constraint like `{id:u64}` take that
+/// type, every other parameter takes any `Display` value.
+///
+/// ## Example
+/// ```
+/// # use fire_http as fire;
+/// use fire::get;
+/// use fire::extractor::PathStr;
+///
+/// #[get("/users/{id}/posts/{*rem}")]
+/// fn user_posts(id: &PathStr, rem: &PathStr) -> String { format!("{id}{rem}") }
+///
+/// let url = user_posts::url(1, "a b/c").query("page", 2);
+/// assert_eq!(url.to_string(), "/users/1/posts/a%20b/c?page=2");
+/// ```
+///
+/// The prefix of a scope is not part of the url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteUrl {
+	path: String,
+	query: String,
+}
+
+impl RouteUrl {
+	/// Fills the parameters of the template, every value gets percent
+	/// encoded, a catch all parameter keeps its slashes.
+	///
+	/// Used by the route macros.
+	///
+	/// ## Panics
+	/// If a parameter of the template has no value.
+	#[doc(hidden)]
+	#[track_caller]
+	pub fn from_template(
+		template: &str,
+		params: &[(&str, &dyn fmt::Display)],
+	) -> Self {
+		let mut path = String::with_capacity(template.len());
+
+		for part in parse_template(template) {
+			match part {
+				TemplatePart::Text(text) => {
+					path.push_str(&text.replace("{{", "{").replace("}}", "}"))
+				}
+				TemplatePart::Param {
+					name, catch_all, ..
+				} => {
+					let value = params
+						.iter()
+						.find(|(n, _)| *n == name)
+						.unwrap_or_else(|| panic!("parameter {name:?} missing"))
+						.1
+						.to_string();
+
+					if catch_all {
+						let segments: Vec<_> = value
+							.split('/')
+							.map(|s| {
+								utf8_percent_encode(s, COMPONENT).to_string()
+							})
+							.collect();
+						path.push_str(&segments.join("/"));
+					} else {
+						path.extend(utf8_percent_encode(&value, COMPONENT));
+					}
+				}
+			}
+		}
+
+		Self {
+			path,
+			query: String::new(),
+		}
+	}
+
+	/// Appends a key value pair to the query, both get percent encoded.
+	pub fn query(
+		mut self,
+		key: impl fmt::Display,
+		value: impl fmt::Display,
+	) -> Self {
+		self.query
+			.push(if self.query.is_empty() { '?' } else { '&' });
+		self.query
+			.extend(utf8_percent_encode(&key.to_string(), COMPONENT));
+		self.query.push('=');
+		self.query
+			.extend(utf8_percent_encode(&value.to_string(), COMPONENT));
+		self
+	}
+
+	/// Returns the path without the query.
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+}
+
+impl fmt::Display for RouteUrl {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}{}", self.path, self.query)
+	}
+}
+
+impl From<RouteUrl> for String {
+	fn from(url: RouteUrl) -> Self {
+		url.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escapes() {
+		let url = RouteUrl::from_template(
+			"/{{raw}}/{name}",
+			&[("name", &"ä?&" as &dyn fmt::Display)],
+		);
+		assert_eq!(url.path(), "/{raw}/%C3%A4%3F%26");
+		assert_eq!(
+			url.query("a b", "=").to_string(),
+			"/{raw}/%C3%A4%3F%26?a%20b=%3D"
+		);
+	}
+
+	#[test]
+	#[should_panic]
+	fn missing_param() {
+		RouteUrl::from_template("/{id}", &[]);
+	}
+}
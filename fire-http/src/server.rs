@@ -1,13 +1,18 @@
-use crate::fire::{self, Wood};
+use crate::fire::{self, Hearth};
+use crate::forwarded::Forwarded;
+use crate::header::PeerAddr;
+use crate::listener::{Listener, Stream};
+use crate::proxy_protocol::{self, ProxyProtocol, Rewind};
+use crate::shutdown::{ShutdownHandle, ShutdownReport, ShutdownSignal};
 use crate::util::PinnedFuture;
 use crate::{Error, FirePit, Result};
 
 use std::convert::Infallible;
-use std::net::SocketAddr;
 use std::pin::Pin;
 use std::result::Result as StdResult;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use hyper_util::server::conn::auto::Builder;
@@ -19,70 +24,359 @@ use hyper::{Request, Response};
 
 pub type HyperRequest = hyper::Request<HyperBody>;
 
-use tokio::net::TcpListener;
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio::task::JoinSet;
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+
+/// How long a client can take to complete the tls handshake.
+#[cfg(feature = "tls")]
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 // todo replace this function once hyper-util is ready
 pub(crate) struct Server {
-	listener: TcpListener,
-	wood: Arc<Wood>,
+	listener: Listener,
+	hearth: Arc<Hearth>,
+	builder: Arc<Builder<TokioExecutor>>,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsAcceptor>,
 }
 
 impl Server {
-	pub(crate) async fn bind(
-		addr: SocketAddr,
-		wood: Arc<Wood>,
-	) -> Result<Self> {
-		let listener = TcpListener::bind(&addr)
-			.await
-			.map_err(Error::from_server_error)?;
-
-		Ok(Self { listener, wood })
+	pub(crate) fn new(
+		listener: Listener,
+		hearth: Arc<Hearth>,
+		builder: Arc<Builder<TokioExecutor>>,
+		#[cfg(feature = "tls")] tls: Option<TlsAcceptor>,
+	) -> Self {
+		Self {
+			listener,
+			hearth,
+			builder,
+			#[cfg(feature = "tls")]
+			tls,
+		}
 	}
 
-	pub fn local_addr(&self) -> Result<SocketAddr> {
-		self.listener.local_addr().map_err(Error::from_server_error)
+	pub fn listener(&self) -> &Listener {
+		&self.listener
 	}
 
-	pub async fn serve(self) -> Result<()> {
+	/// Serves connections until the shutdown gets triggered, then waits
+	/// until every connection is closed or the timeout elapses.
+	pub async fn serve(
+		self,
+		shutdown: ShutdownHandle,
+		timeout: Duration,
+	) -> Result<ShutdownReport> {
+		let mut connections = JoinSet::new();
+		let mut signal = shutdown.signal();
+		let name = self.listener.name_arc();
+
+		let limits = self.hearth.connections().clone();
+		let ctx = Arc::new(ConnectionContext {
+			hearth: self.hearth,
+			builder: self.builder,
+			listener: name.clone(),
+			proxy_protocol: self.listener.proxy_protocol(),
+			#[cfg(feature = "tls")]
+			tls: self.tls,
+		});
+
 		loop {
-			let (stream, address) = self
-				.listener
-				.accept()
-				.await
-				.map_err(Error::from_server_error)?;
+			let accept = async {
+				// if the connection limit is reached wait until another
+				// connection closes
+				let permit = limits.ready().await;
+				self.listener.accept().await.map(|a| (a, permit))
+			};
 
-			let io = TokioIo::new(stream);
+			let accepted = tokio::select! {
+				accepted = accept => accepted,
+				// remove finished connections
+				Some(_) = connections.join_next() => continue,
+				_ = signal.wait() => break,
+			};
 
-			let service = FireService {
-				wood: self.wood.clone(),
-				address,
+			let ((stream, peer), permit) = match accepted {
+				Ok(a) => a,
+				Err(e) => {
+					// the open connections should not be affected
+					connections.detach_all();
+					return Err(Error::from_server_error(e));
+				}
 			};
 
-			tokio::task::spawn(async move {
-				if let Err(err) = Builder::new(TokioExecutor::new())
-					.serve_connection_with_upgrades(io, service)
-					.await
-				{
-					tracing::error!(error = ?err, "Error serving connection: {err}");
+			// everything else is done in the connection task so a slow
+			// client cannot block the accept loop
+			connections.spawn(ctx.clone().handle(
+				stream,
+				peer,
+				permit,
+				shutdown.signal(),
+			));
+		}
+
+		// stop accepting new connections
+		drop(self.listener);
+
+		let open = connections.len();
+		info!("Shutting down {name}, waiting for {open} connections to close");
+
+		let drain = async { while connections.join_next().await.is_some() {} };
+
+		let force_closed = match tokio::time::timeout(timeout, drain).await {
+			Ok(_) => 0,
+			Err(_) => {
+				let force_closed = connections.len();
+				warn!("Forcefully closing {force_closed} connections");
+				connections.shutdown().await;
+
+				force_closed
+			}
+		};
+
+		Ok(ShutdownReport {
+			drained: open - force_closed,
+			force_closed,
+		})
+	}
+}
+
+/// Everything needed to serve a connection accepted by a listener.
+struct ConnectionContext {
+	hearth: Arc<Hearth>,
+	builder: Arc<Builder<TokioExecutor>>,
+	listener: Arc<str>,
+	proxy_protocol: Option<ProxyProtocol>,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsAcceptor>,
+}
+
+impl ConnectionContext {
+	async fn handle(
+		self: Arc<Self>,
+		stream: Stream,
+		mut peer: PeerAddr,
+		permit: Option<OwnedSemaphorePermit>,
+		signal: ShutdownSignal,
+	) {
+		// the slot is reserved before reading a proxy protocol header so
+		// connections which never send one count towards the limit
+		let connections = self.hearth.connections();
+		let Some(guard) = connections.reserve(permit) else {
+			debug!(?peer, "connection limit reached, closing connection");
+			return;
+		};
+
+		let stream = match self.proxy_protocol {
+			Some(mode) => match proxy_protocol::read_header(stream, mode).await
+			{
+				Ok((stream, source)) => {
+					if let Some(source) = source {
+						peer = PeerAddr::Tcp(source);
+					}
+					stream
+				}
+				Err(e) => {
+					debug!(?peer, error = ?e, "invalid proxy protocol header");
+					return;
+				}
+			},
+			None => Rewind::new(stream, Bytes::new()),
+		};
+
+		let Some(_guard) = guard.bind(&peer) else {
+			debug!(?peer, "connection limit reached, closing connection");
+			return;
+		};
+
+		// upgraded connections like websockets are served by this task, so
+		// the shutdown waits for them and they keep the guard
+		let (upgrades, tasks) = Upgrades::new(signal.clone());
+
+		#[cfg(feature = "tls")]
+		if let Some(tls) = &self.tls {
+			let service = FireService::from_connection(
+				self.hearth.clone(),
+				peer,
+				Some(self.listener.clone()),
+				true,
+			)
+			.upgrades(upgrades);
+
+			let accept = tls.accept(stream);
+			match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, accept).await {
+				Ok(Ok(stream)) => {
+					let conn = serve_connection(
+						stream,
+						&self.builder,
+						service,
+						signal,
+					);
+					tokio::join!(conn, run_upgrades(tasks));
 				}
-			});
+				Ok(Err(e)) => warn!(error = ?e, "tls handshake failed"),
+				Err(_) => debug!("tls handshake timed out"),
+			}
+
+			return;
+		}
+
+		let service = FireService::from_connection(
+			self.hearth.clone(),
+			peer,
+			Some(self.listener.clone()),
+			false,
+		)
+		.upgrades(upgrades);
+
+		let conn = serve_connection(stream, &self.builder, service, signal);
+		tokio::join!(conn, run_upgrades(tasks));
+	}
+}
+
+type UpgradeTask = PinnedFuture<'static, ()>;
+
+/// Sends the tasks of upgraded connections to the task serving the
+/// connection.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "ws"), allow(dead_code))]
+pub(crate) struct Upgrades {
+	tx: mpsc::UnboundedSender<UpgradeTask>,
+	/// passed to the tasks so they can close themselves on shutdown
+	pub signal: ShutdownSignal,
+}
+
+impl Upgrades {
+	fn new(
+		signal: ShutdownSignal,
+	) -> (Self, mpsc::UnboundedReceiver<UpgradeTask>) {
+		let (tx, rx) = mpsc::unbounded_channel();
+		(Self { tx, signal }, rx)
+	}
+
+	#[cfg_attr(not(feature = "ws"), allow(dead_code))]
+	pub fn spawn(&self, task: UpgradeTask) {
+		if self.tx.send(task).is_err() {
+			debug!("connection closed before the upgrade");
 		}
 	}
 }
 
+/// Runs the tasks of upgraded connections until every sender was dropped
+/// and every task finished.
+///
+/// Dropping the future aborts the tasks.
+async fn run_upgrades(mut rx: mpsc::UnboundedReceiver<UpgradeTask>) {
+	let mut tasks = JoinSet::new();
+
+	loop {
+		tokio::select! {
+			Some(task) = rx.recv() => {
+				tasks.spawn(task);
+			}
+			Some(_) = tasks.join_next() => {}
+			else => break,
+		}
+	}
+}
+
+async fn serve_connection<I>(
+	io: I,
+	builder: &Builder<TokioExecutor>,
+	service: FireService,
+	mut signal: ShutdownSignal,
+) where
+	I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	let io = TokioIo::new(io);
+
+	let conn = builder.serve_connection_with_upgrades(io, service);
+	tokio::pin!(conn);
+
+	let res = tokio::select! {
+		res = conn.as_mut() => res,
+		_ = signal.wait() => {
+			// finishes the in-flight requests and closes the connection
+			conn.as_mut().graceful_shutdown();
+			conn.await
+		}
+	};
+
+	if let Err(err) = res {
+		tracing::error!(error = ?err, "Error serving connection: {err}");
+	}
+}
+
+/// Information about the connection a request was received on.
+///
+/// Gets stored in the extensions of every hyper request.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionInfo {
+	pub peer: PeerAddr,
+	/// the name of the listener which accepted the connection
+	pub listener: Option<Arc<str>>,
+	/// whether the connection is encrypted with tls
+	pub secure: bool,
+	/// what a trusted proxy sent about the client
+	pub forwarded: Option<Forwarded>,
+	#[cfg_attr(not(feature = "ws"), allow(dead_code))]
+	pub upgrades: Option<Upgrades>,
+}
+
 pub struct FireService {
-	wood: Arc<Wood>,
-	address: SocketAddr,
+	hearth: Arc<Hearth>,
+	peer: PeerAddr,
+	listener: Option<Arc<str>>,
+	secure: bool,
+	upgrades: Option<Upgrades>,
 }
 
 impl FireService {
 	/// Creates a new FireService which can be passed to a hyper server.
-	pub fn new(pit: FirePit, address: SocketAddr) -> Self {
+	pub fn new(pit: FirePit, address: impl Into<PeerAddr>) -> Self {
+		Self::from_connection(pit.hearth, address.into(), None, false)
+	}
+
+	fn from_connection(
+		hearth: Arc<Hearth>,
+		peer: PeerAddr,
+		listener: Option<Arc<str>>,
+		secure: bool,
+	) -> Self {
 		Self {
-			wood: pit.wood,
-			address,
+			hearth,
+			peer,
+			listener,
+			secure,
+			upgrades: None,
 		}
 	}
+
+	fn upgrades(mut self, upgrades: Upgrades) -> Self {
+		self.upgrades = Some(upgrades);
+		self
+	}
+
+	/// Sets the listener name which is exposed with
+	/// `RequestHeader::listener`.
+	pub fn listener(mut self, name: impl AsRef<str>) -> Self {
+		self.listener = Some(name.as_ref().into());
+		self
+	}
+
+	/// Marks every request served by this service as received over tls.
+	///
+	/// Use this if you terminate tls yourself so that the request uri
+	/// contains the https scheme.
+	pub fn secure(mut self, secure: bool) -> Self {
+		self.secure = secure;
+		self
+	}
 }
 
 impl Service<Request<Incoming>> for FireService {
@@ -90,9 +384,26 @@ impl Service<Request<Incoming>> for FireService {
 	type Error = Infallible;
 	type Future = PinnedFuture<'static, StdResult<Self::Response, Self::Error>>;
 
-	fn call(&self, req: Request<Incoming>) -> Self::Future {
-		let wood = self.wood.clone();
-		let address = self.address;
+	fn call(&self, mut req: Request<Incoming>) -> Self::Future {
+		let forwarded = self.hearth.trusted_proxies().and_then(|proxies| {
+			proxies.resolve(self.peer.socket_addr(), req.headers())
+		});
+
+		let address = forwarded
+			.as_ref()
+			.and_then(|f| f.client)
+			.unwrap_or_else(|| self.peer.to_socket_addr());
+
+		req.extensions_mut().insert(ConnectionInfo {
+			peer: self.peer.clone(),
+			listener: self.listener.clone(),
+			secure: self.secure,
+			forwarded,
+			upgrades: self.upgrades.clone(),
+		});
+
+		// the request keeps using this wood even if it gets swapped
+		let wood = self.hearth.wood();
 		PinnedFuture::new(async move {
 			fire::route_hyper(&wood, req, address).await
 		})
@@ -0,0 +1,95 @@
+//! Graceful shutdown of a running `Fire`.
+
+use std::future::pending;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+pub(crate) const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A handle which can stop a running `Fire`.
+///
+/// Once `shutdown` gets called the server stops accepting new connections,
+/// let's every open connection finish it's in-flight requests and then
+/// returns from `ignite`.
+///
+/// Upgraded connections like websockets are waited for until they get
+/// closed or the shutdown timeout elapses, they can use a `ShutdownSignal`
+/// to close themselves earlier.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+	inner: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+	pub(crate) fn new() -> Self {
+		Self {
+			inner: Arc::new(watch::Sender::new(false)),
+		}
+	}
+
+	/// Starts the graceful shutdown.
+	///
+	/// Calling this multiple times has no additional effect.
+	pub fn shutdown(&self) {
+		self.inner.send_replace(true);
+	}
+
+	/// Returns true if the shutdown was already started.
+	pub fn is_shutdown(&self) -> bool {
+		*self.inner.borrow()
+	}
+
+	pub(crate) fn signal(&self) -> ShutdownSignal {
+		ShutdownSignal {
+			inner: self.inner.subscribe(),
+		}
+	}
+}
+
+/// Notifies when a graceful shutdown was started.
+///
+/// Websocket handlers get it with `WebSocket::shutdown_signal` so they can
+/// close their connection cleanly instead of being closed when the shutdown
+/// timeout elapses.
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+	inner: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+	/// Returns a signal which never fires, used for connections which were
+	/// not accepted by a `Fire`.
+	#[cfg_attr(not(feature = "ws"), allow(dead_code))]
+	pub(crate) fn never() -> Self {
+		Self {
+			inner: watch::channel(false).1,
+		}
+	}
+
+	/// Waits until the shutdown was started.
+	pub async fn wait(&mut self) {
+		if self.inner.wait_for(|shutdown| *shutdown).await.is_err() {
+			// the sender can only be dropped if no handle exists anymore
+			pending().await
+		}
+	}
+
+	/// Returns true if the shutdown was already started.
+	pub fn is_shutdown(&self) -> bool {
+		*self.inner.borrow()
+	}
+}
+
+/// Statistics about a finished graceful shutdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ShutdownReport {
+	/// The amount of connections which finished by themselves after the
+	/// shutdown was started.
+	pub drained: usize,
+	/// The amount of connections which were still open when the shutdown
+	/// timeout elapsed and which were closed forcefully.
+	pub force_closed: usize,
+}
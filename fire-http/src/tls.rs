@@ -0,0 +1,98 @@
+//! Tls termination with rustls.
+
+use crate::{Error, Result};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+/// The certificate chain and private key used to serve https.
+///
+/// ## Example
+/// ```no_run
+/// # use fire_http as fire;
+/// use fire::tls::TlsConfig;
+///
+/// # async fn run() {
+/// let tls = TlsConfig::from_pem_files("./cert.pem", "./key.pem").await;
+/// let tls = tls.unwrap();
+///
+/// let mut server = fire::build("0.0.0.0:443").await.unwrap();
+/// server.tls(tls);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TlsConfig {
+	certs: Vec<CertificateDer<'static>>,
+	key: PrivateKeyDer<'static>,
+}
+
+impl TlsConfig {
+	/// Creates a `TlsConfig` from a pem encoded certificate chain and a pem
+	/// encoded private key.
+	pub fn from_pem(
+		certs: impl AsRef<[u8]>,
+		key: impl AsRef<[u8]>,
+	) -> Result<Self> {
+		let certs = rustls_pemfile::certs(&mut certs.as_ref())
+			.collect::<std::result::Result<Vec<_>, _>>()
+			.map_err(Error::from_server_error)?;
+
+		if certs.is_empty() {
+			return Err(Error::from_server_error("no certificate found"));
+		}
+
+		let key = rustls_pemfile::private_key(&mut key.as_ref())
+			.map_err(Error::from_server_error)?
+			.ok_or_else(|| Error::from_server_error("no private key found"))?;
+
+		Ok(Self { certs, key })
+	}
+
+	/// Reads a pem encoded certificate chain and a pem encoded private key
+	/// from the filesystem.
+	pub async fn from_pem_files(
+		certs: impl AsRef<Path>,
+		key: impl AsRef<Path>,
+	) -> Result<Self> {
+		let certs = tokio::fs::read(certs)
+			.await
+			.map_err(Error::from_server_error)?;
+		let key = tokio::fs::read(key)
+			.await
+			.map_err(Error::from_server_error)?;
+
+		Self::from_pem(certs, key)
+	}
+
+	/// Creates a `TlsConfig` from an already decoded certificate chain and
+	/// private key.
+	pub fn from_der(
+		certs: Vec<CertificateDer<'static>>,
+		key: PrivateKeyDer<'static>,
+	) -> Self {
+		Self { certs, key }
+	}
+
+	pub(crate) fn into_acceptor(self) -> Result<TlsAcceptor> {
+		let provider = Arc::new(ring::default_provider());
+		let mut config = ServerConfig::builder_with_provider(provider)
+			.with_safe_default_protocol_versions()
+			.map_err(Error::from_server_error)?
+			.with_no_client_auth()
+			.with_single_cert(self.certs, self.key)
+			.map_err(Error::from_server_error)?;
+
+		// let the client know which protocols the auto builder can serve
+		#[cfg(feature = "http2")]
+		config.alpn_protocols.push(b"h2".to_vec());
+		#[cfg(feature = "http1")]
+		config.alpn_protocols.push(b"http/1.1".to_vec());
+
+		Ok(TlsAcceptor::from(Arc::new(config)))
+	}
+}
@@ -1,8 +1,11 @@
-use crate::header::{HeaderValues, RequestHeader, Uri, HOST};
+use crate::header::{self, HeaderValues, PeerAddr, RequestHeader, Uri, HOST};
+use crate::server::ConnectionInfo;
 
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use hyper::http::uri::{Authority, Scheme};
+use hyper::http::Extensions;
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum HeaderError {
@@ -23,14 +26,14 @@ pub fn convert_hyper_parts_to_fire_header(
 	address: SocketAddr,
 ) -> Result<RequestHeader> {
 	let values = HeaderValues::from_inner(parts.headers);
-	let uri = fill_uri(parts.uri, &values)?;
-
-	Ok(RequestHeader {
-		address,
-		method: parts.method,
-		uri,
-		values,
-	})
+	let uri = fill_uri(parts.uri, &values, &parts.extensions)?;
+
+	let mut header = RequestHeader::new(address, parts.method, uri, values);
+	header.peer = peer(&parts.extensions, address);
+	header.listener = listener(&parts.extensions);
+	header.extensions = extensions(&parts.extensions);
+
+	Ok(header)
 }
 
 pub fn convert_hyper_req_to_fire_header<B>(
@@ -38,29 +41,94 @@ pub fn convert_hyper_req_to_fire_header<B>(
 	address: SocketAddr,
 ) -> Result<RequestHeader> {
 	let values = HeaderValues::from_inner(req.headers().clone());
-	let uri = fill_uri(req.uri().clone(), &values)?;
-
-	Ok(RequestHeader {
-		address,
-		method: req.method().clone(),
-		uri,
-		values,
-	})
+	let uri = fill_uri(req.uri().clone(), &values, req.extensions())?;
+
+	let method = req.method().clone();
+	let mut header = RequestHeader::new(address, method, uri, values);
+	header.peer = peer(req.extensions(), address);
+	header.listener = listener(req.extensions());
+	header.extensions = extensions(req.extensions());
+
+	Ok(header)
+}
+
+/// Returns the extensions which were added by middleware before a raw
+/// route was called.
+fn extensions(extensions: &Extensions) -> header::Extensions {
+	extensions
+		.get::<header::Extensions>()
+		.cloned()
+		.unwrap_or_default()
+}
+
+/// Returns the peer of the connection, falls back to the address if the
+/// request did not go through a `FireService`.
+fn peer(extensions: &Extensions, address: SocketAddr) -> PeerAddr {
+	match extensions.get::<ConnectionInfo>() {
+		Some(info) => info.peer.clone(),
+		None => address.into(),
+	}
+}
+
+/// Returns the name of the listener which accepted the connection.
+fn listener(extensions: &Extensions) -> Option<Arc<str>> {
+	extensions
+		.get::<ConnectionInfo>()
+		.and_then(|info| info.listener.clone())
 }
 
-fn fill_uri(uri: Uri, headers: &HeaderValues) -> Result<Uri> {
+/// Returns the scheme the client used, either from a trusted proxy or from
+/// the connection the request was received on.
+fn scheme(extensions: &Extensions) -> Scheme {
+	let Some(info) = extensions.get::<ConnectionInfo>() else {
+		return Scheme::HTTP;
+	};
+
+	match info.forwarded.as_ref().and_then(|f| f.scheme.clone()) {
+		Some(scheme) => scheme,
+		None if info.secure => Scheme::HTTPS,
+		None => Scheme::HTTP,
+	}
+}
+
+/// Returns the host a trusted proxy received the request on.
+fn forwarded_host(extensions: &Extensions) -> Option<Authority> {
+	extensions
+		.get::<ConnectionInfo>()
+		.and_then(|info| info.forwarded.as_ref())
+		.and_then(|f| f.host.clone())
+}
+
+/// Returns the host the client used without the port, either from a trusted
+/// proxy, the host header or the uri.
+pub(crate) fn request_host<B>(req: &hyper::Request<B>) -> Option<Authority> {
+	forwarded_host(req.extensions())
+		.or_else(|| {
+			let host = req.headers().get(HOST)?;
+			Authority::try_from(host.as_bytes()).ok()
+		})
+		.or_else(|| req.uri().authority().cloned())
+}
+
+fn fill_uri(
+	uri: Uri,
+	headers: &HeaderValues,
+	extensions: &Extensions,
+) -> Result<Uri> {
 	let mut parts = uri.into_parts();
-	// Try to detect it
-	// todo how would it be possible to detect http or https
-	parts.scheme = Some(Scheme::HTTP);
+	parts.scheme = Some(scheme(extensions));
 
 	// get host infos
-	let host = headers.get(HOST).ok_or(HeaderError::NoHost)?;
-
-	parts.authority = Some(
-		Authority::try_from(host.as_bytes())
-			.map_err(|_| HeaderError::HostInvalid)?,
-	);
+	let authority = match forwarded_host(extensions) {
+		Some(host) => host,
+		None => match headers.get(HOST) {
+			Some(host) => Authority::try_from(host.as_bytes())
+				.map_err(|_| HeaderError::HostInvalid)?,
+			// http2 sends the host as the :authority pseudo header
+			None => parts.authority.take().ok_or(HeaderError::NoHost)?,
+		},
+	};
+	parts.authority = Some(authority);
 
 	Uri::from_parts(parts).map_err(|_| HeaderError::Uri)
 }
@@ -1,5 +1,7 @@
 use crate::fire::RequestConfigs;
-use crate::header::{ContentType, CONTENT_TYPE};
+use crate::header::{
+	ContentType, HeaderValues, RequestHeader, CONTENT_TYPE, VARY,
+};
 use crate::server::HyperRequest;
 use crate::{Body, Request, Response};
 
@@ -14,7 +16,7 @@ use tracing::error;
 mod header;
 use header::convert_hyper_parts_to_fire_header;
 pub use header::convert_hyper_req_to_fire_header;
-pub(crate) use header::HeaderError;
+pub(crate) use header::{request_host, HeaderError};
 
 use types::body::BodyHttp;
 
@@ -42,19 +44,35 @@ impl<O> Future for PinnedFuture<'_, O> {
 
 // private stuff
 
+/// Adds the header name to the `Vary` header if it is not already listed.
+pub(crate) fn add_vary(values: &mut HeaderValues, name: &str) {
+	let vary = match values.get_str(VARY) {
+		Some(vary)
+			if vary.split(',').any(|v| v.trim().eq_ignore_ascii_case(name)) =>
+		{
+			return
+		}
+		Some(vary) => format!("{vary}, {name}"),
+		None => name.to_string(),
+	};
+	values.insert(VARY, vary);
+}
+
+/// The configs of the body are returned by `configs` from the header.
 pub(crate) fn convert_hyper_req_to_fire_req(
 	hyper_req: HyperRequest,
 	address: SocketAddr,
-	configs: &RequestConfigs,
+	configs: impl FnOnce(&RequestHeader) -> RequestConfigs,
 ) -> Result<Request, HeaderError> {
 	let (parts, body) = hyper_req.into_parts();
 
+	let header = convert_hyper_parts_to_fire_header(parts, address)?;
+
+	let configs = configs(&header);
 	let mut body = Body::from(body);
 	body.set_size_limit(Some(configs.size_limit));
 	body.set_timeout(Some(configs.timeout));
 
-	let header = convert_hyper_parts_to_fire_header(parts, address)?;
-
 	Ok(Request::new(header, body))
 }
 
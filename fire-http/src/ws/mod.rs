@@ -2,10 +2,13 @@
 pub mod util;
 
 use crate::extractor::Extractor;
+use crate::shutdown::ShutdownSignal;
 
 use std::convert::Infallible;
 use std::fmt;
+use std::io;
 use std::str::Utf8Error;
+use std::time::Duration;
 
 use hyper_util::rt::TokioIo;
 use tracing::warn;
@@ -15,7 +18,7 @@ pub use hyper::upgrade;
 
 use tokio_tungstenite::tungstenite;
 use tokio_tungstenite::WebSocketStream;
-use tungstenite::protocol::Role;
+use tungstenite::protocol::{Role, WebSocketConfig};
 
 // rexport
 use tungstenite::protocol::Message as ProtMessage;
@@ -114,35 +117,94 @@ impl From<Message> for ProtMessage {
 #[derive(Debug)]
 pub struct WebSocket {
 	inner: WebSocketStream<TokioIo<upgrade::Upgraded>>,
+	timeout: Option<Duration>,
+	signal: ShutdownSignal,
 }
 
 impl WebSocket {
 	pub async fn new(upgraded: upgrade::Upgraded) -> Self {
+		Self::with_limits(upgraded, None, None).await
+	}
+
+	/// Creates a websocket which rejects messages bigger than the size limit
+	/// and where `receive` fails if no message arrives within the timeout.
+	///
+	/// Used by the `ws` macro with `size_limit` and `timeout`.
+	pub async fn with_limits(
+		upgraded: upgrade::Upgraded,
+		size_limit: Option<usize>,
+		timeout: Option<Duration>,
+	) -> Self {
+		let config = size_limit.map(|size_limit| WebSocketConfig {
+			max_message_size: Some(size_limit),
+			max_frame_size: Some(size_limit),
+			..Default::default()
+		});
+
 		Self {
 			inner: WebSocketStream::from_raw_socket(
 				TokioIo::new(upgraded),
 				Role::Server,
-				None,
+				config,
 			)
 			.await,
+			timeout,
+			signal: ShutdownSignal::never(),
 		}
 	}
 
+	/// Sets the signal returned by `shutdown_signal`.
+	///
+	/// Used by the `ws` macro.
+	#[doc(hidden)]
+	pub fn with_shutdown(mut self, signal: ShutdownSignal) -> Self {
+		self.signal = signal;
+		self
+	}
+
+	/// Returns a signal which fires once a graceful shutdown was started,
+	/// so the connection can be closed before the shutdown timeout elapses.
+	///
+	/// The signal never fires if the connection was not accepted by a
+	/// `Fire`.
+	pub fn shutdown_signal(&self) -> ShutdownSignal {
+		self.signal.clone()
+	}
+
 	// used for tests
 	#[doc(hidden)]
 	pub fn from_raw(
 		inner: WebSocketStream<TokioIo<upgrade::Upgraded>>,
 	) -> Self {
-		Self { inner }
+		Self {
+			inner,
+			timeout: None,
+			signal: ShutdownSignal::never(),
+		}
 	}
 
 	/// Handles Ping and Pong messages
 	///
+	/// Returns an io error with the kind `TimedOut` if a timeout was set and
+	/// no message arrived in time.
+	///
 	/// never returns Error::ConnectionClose | Error::AlreadyClosed
 	pub async fn receive(&mut self) -> Result<Option<Message>, Error> {
 		// loop used to handle Message::Pong | Message::Ping
 		loop {
-			let res = self.inner.next().await.transpose();
+			let next = self.inner.next();
+			let res = match self.timeout {
+				Some(timeout) => {
+					tokio::time::timeout(timeout, next).await.map_err(|_| {
+						Error::Io(io::Error::new(
+							io::ErrorKind::TimedOut,
+							"no message received within the timeout",
+						))
+					})?
+				}
+				None => next.await,
+			};
+			let res = res.transpose();
 			return match res {
 				Ok(None) => Ok(None),
 				Ok(Some(ProtMessage::Text(t))) => Ok(Some(Message::Text(t))),
@@ -7,18 +7,45 @@ use crate::header::{
 	StatusCode, CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY,
 	SEC_WEBSOCKET_VERSION, UPGRADE,
 };
-use crate::server::HyperRequest;
-use crate::util::convert_hyper_req_to_fire_header;
+use crate::server::{ConnectionInfo, HyperRequest};
+use crate::shutdown::ShutdownSignal;
+use crate::util::{convert_hyper_req_to_fire_header, PinnedFuture};
 use crate::{Error, Response, Result};
 
+use std::future::Future;
+
 use tracing::error;
 
 use sha1::Digest;
 
 use hyper::upgrade::OnUpgrade;
 
+/// Spawns the task which handles the upgraded connection, the task gets
+/// the signal of a graceful shutdown.
+///
+/// If the connection was accepted by fire the task is part of the
+/// connection, so a graceful shutdown waits for it or closes it.
 #[doc(hidden)]
-pub use tokio::task::spawn;
+pub fn spawn<F, Fut>(req: &HyperRequest, task: F)
+where
+	F: FnOnce(ShutdownSignal) -> Fut,
+	Fut: Future<Output = ()> + Send + 'static,
+{
+	let upgrades = req
+		.extensions()
+		.get::<ConnectionInfo>()
+		.and_then(|info| info.upgrades.as_ref());
+
+	match upgrades {
+		Some(upgrades) => {
+			let task = task(upgrades.signal.clone());
+			upgrades.spawn(PinnedFuture::new(task))
+		}
+		None => {
+			tokio::task::spawn(task(ShutdownSignal::never()));
+		}
+	}
+}
 
 use base64::prelude::{Engine as _, BASE64_STANDARD};
 use types::header::RequestHeader;
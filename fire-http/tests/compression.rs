@@ -0,0 +1,254 @@
+use fire_http as fire;
+
+use fire::compression::{Compression, Encoding};
+use fire::header::{Mime, RequestHeader, ResponseHeader, StatusCode};
+use fire::resources::Resources;
+use fire::routes::Catcher;
+use fire::util::PinnedFuture;
+use fire::{get, Body, Request, Response};
+
+use std::io::Cursor;
+
+use async_compression::tokio::bufread::{
+	BrotliDecoder, DeflateDecoder, GzipDecoder, ZstdDecoder,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+#[macro_use]
+mod util;
+
+fn text() -> String {
+	"hello compression ".repeat(200)
+}
+
+#[get("/text")]
+fn get_text() -> String {
+	text()
+}
+
+#[get("/small")]
+fn small() -> &'static str {
+	"small"
+}
+
+#[get("/image")]
+fn image() -> Response {
+	Response::builder()
+		.content_type("image/png")
+		.body(text())
+		.build()
+}
+
+#[get("/etag")]
+fn etag() -> Response {
+	Response::builder()
+		.content_type(Mime::TEXT)
+		.header("etag", "\"abc\"")
+		.body(text())
+		.build()
+}
+
+#[get("/no-transform")]
+fn no_transform() -> Response {
+	Response::builder()
+		.content_type(Mime::TEXT)
+		.header("cache-control", "public, No-Transform")
+		.body(text())
+		.build()
+}
+
+#[get("/stream")]
+fn stream() -> Response {
+	Response::builder()
+		.content_type(Mime::TEXT)
+		.body(Body::from_async_reader(Cursor::new(text().into_bytes())))
+		.build()
+}
+
+macro_rules! request {
+	($addr:expr, $uri:expr, $accept:expr) => {
+		make_request!("GET", $addr, $uri, |builder| {
+			builder
+				.header("accept-encoding", $accept)
+				.body(fire::Body::new().into_http_body())
+				.unwrap()
+		})
+	};
+}
+
+async fn decode(encoding: &str, body: Body) -> String {
+	let bytes = body.into_bytes().await.unwrap();
+	let reader = &bytes[..];
+	let mut reader: Box<dyn AsyncRead + Unpin> = match encoding {
+		"br" => Box::new(BrotliDecoder::new(reader)),
+		"zstd" => Box::new(ZstdDecoder::new(reader)),
+		"gzip" => Box::new(GzipDecoder::new(reader)),
+		"deflate" => Box::new(DeflateDecoder::new(reader)),
+		e => panic!("unknown encoding {e}"),
+	};
+
+	let mut s = String::new();
+	reader.read_to_string(&mut s).await.unwrap();
+	s
+}
+
+#[tokio::test]
+async fn negotiation() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Compression::new());
+		builder.add_route(get_text);
+	});
+
+	let cases = [
+		("gzip, deflate, br, zstd", "br"),
+		("gzip;q=0.5, zstd;q=0.8", "zstd"),
+		("deflate, gzip;q=0.9", "deflate"),
+		("gzip", "gzip"),
+	];
+
+	for (accept, encoding) in cases {
+		let resp = request!(addr, "/text", accept)
+			.await
+			.assert_status(200)
+			.assert_header("content-encoding", encoding)
+			.assert_header("vary", "accept-encoding")
+			.assert_not_header("content-length");
+
+		assert_eq!(decode(encoding, resp.into_body()).await, text());
+	}
+
+	// identity
+	request!(addr, "/text", "identity")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_header("vary", "accept-encoding")
+		.assert_header("content-length", text().len().to_string())
+		.assert_body_str(&text())
+		.await;
+}
+
+#[tokio::test]
+async fn skipped() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(
+			Compression::new().encodings([Encoding::Gzip, Encoding::Zstd]),
+		);
+		builder.add_route(get_text);
+		builder.add_route(small);
+		builder.add_route(image);
+	});
+
+	request!(addr, "/small", "gzip")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_not_header("vary")
+		.assert_body_str("small")
+		.await;
+
+	request!(addr, "/image", "gzip")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_body_str(&text())
+		.await;
+
+	// brotli is not enabled
+	request!(addr, "/text", "br")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_header("vary", "accept-encoding");
+
+	make_request!("GET", addr, "/text")
+		.await
+		.assert_not_header("content-encoding");
+}
+
+#[tokio::test]
+async fn caching() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Compression::new());
+		builder.add_route(etag);
+		builder.add_route(no_transform);
+	});
+
+	request!(addr, "/etag", "gzip")
+		.await
+		.assert_header("content-encoding", "gzip")
+		.assert_header("etag", "W/\"abc\"");
+
+	request!(addr, "/etag", "identity")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_header("etag", "\"abc\"");
+
+	request!(addr, "/no-transform", "gzip")
+		.await
+		.assert_not_header("content-encoding")
+		.assert_body_str(&text())
+		.await;
+}
+
+#[tokio::test]
+async fn streaming() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Compression::new());
+		builder.add_route(stream);
+	});
+
+	let resp = request!(addr, "/stream", "gzip")
+		.await
+		.assert_status(200)
+		.assert_header("content-encoding", "gzip");
+
+	assert_eq!(decode("gzip", resp.into_body()).await, text());
+}
+
+/// Replaces the body of not found responses.
+struct NotFound(String);
+
+impl Catcher for NotFound {
+	fn check(&self, _req: &RequestHeader, resp: &ResponseHeader) -> bool {
+		resp.status_code == StatusCode::NOT_FOUND
+	}
+
+	fn call<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<()>> {
+		PinnedFuture::new(async move {
+			*resp = Response::builder()
+				.status_code(StatusCode::NOT_FOUND)
+				.content_type(Mime::TEXT)
+				.body(self.0.clone())
+				.build();
+			Ok(())
+		})
+	}
+}
+
+#[tokio::test]
+async fn catcher() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Compression::new());
+		builder.add_catcher(NotFound(text()));
+	});
+
+	let resp = request!(addr, "/unknown", "gzip")
+		.await
+		.assert_status(404)
+		.assert_header("content-encoding", "gzip");
+	assert_eq!(decode("gzip", resp.into_body()).await, text());
+
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Compression::new());
+		builder.add_catcher(NotFound("custom not found page".into()));
+	});
+
+	request!(addr, "/unknown", "gzip")
+		.await
+		.assert_status(404)
+		.assert_not_header("content-encoding")
+		.assert_body_str("custom not found page")
+		.await;
+}
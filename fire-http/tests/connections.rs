@@ -0,0 +1,125 @@
+use fire_http as fire;
+
+use fire::connections::Overflow;
+use fire::{get, Body};
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use hyper::client::conn::http1::SendRequest;
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout};
+use types::body::BodyHttp;
+
+#[get("/")]
+fn hello() -> &'static str {
+	"hello"
+}
+
+async fn build_fire(f: impl FnOnce(&mut fire::FireBuilder)) -> fire::Fire {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_route(hello);
+	f(&mut builder);
+	builder.build().await.unwrap()
+}
+
+/// Opens a keep-alive connection.
+async fn connect(addr: SocketAddr) -> SendRequest<BodyHttp> {
+	let stream = TcpStream::connect(addr).await.unwrap();
+	let (sender, conn) =
+		hyper::client::conn::http1::handshake(TokioIo::new(stream))
+			.await
+			.unwrap();
+	tokio::task::spawn(conn);
+
+	sender
+}
+
+async fn request(sender: &mut SendRequest<BodyHttp>) -> hyper::Result<u16> {
+	let req = hyper::Request::builder()
+		.uri("/")
+		.header("host", "localhost")
+		.body(Body::new().into_http_body())
+		.unwrap();
+
+	sender.send_request(req).await.map(|r| r.status().as_u16())
+}
+
+#[tokio::test]
+async fn reject_over_max_connections() {
+	let fire = build_fire(|b| {
+		b.max_connections(1);
+		b.connection_overflow(Overflow::Reject);
+	})
+	.await;
+	let addr = fire.local_addr().unwrap();
+	let pit = fire.pit();
+	tokio::task::spawn(fire.ignite());
+
+	let mut first = connect(addr).await;
+	assert_eq!(request(&mut first).await.unwrap(), 200);
+
+	let mut second = connect(addr).await;
+	assert!(request(&mut second).await.is_err());
+
+	let stats = pit.connections();
+	assert_eq!(stats.current, 1);
+	assert_eq!(stats.ips, 1);
+	assert_eq!(stats.rejected, 1);
+	assert_eq!(pit.connections_from(Ipv4Addr::LOCALHOST.into()), 1);
+
+	// after the first connection is closed a new one is accepted
+	drop(first);
+	sleep(Duration::from_millis(50)).await;
+	assert_eq!(pit.connections().current, 0);
+
+	let mut third = connect(addr).await;
+	assert_eq!(request(&mut third).await.unwrap(), 200);
+}
+
+#[tokio::test]
+async fn wait_over_max_connections() {
+	let fire = build_fire(|b| b.max_connections(1)).await;
+	let addr = fire.local_addr().unwrap();
+	let pit = fire.pit();
+	tokio::task::spawn(fire.ignite());
+
+	let mut first = connect(addr).await;
+	assert_eq!(request(&mut first).await.unwrap(), 200);
+
+	// the second connection waits in the backlog
+	let second = tokio::task::spawn(async move {
+		let mut second = connect(addr).await;
+		request(&mut second).await.unwrap()
+	});
+
+	sleep(Duration::from_millis(50)).await;
+	assert!(!second.is_finished());
+	assert_eq!(pit.connections().current, 1);
+
+	drop(first);
+	let status = timeout(Duration::from_secs(2), second).await.unwrap();
+	assert_eq!(status.unwrap(), 200);
+	assert_eq!(pit.connections().rejected, 0);
+}
+
+#[tokio::test]
+async fn reject_over_max_connections_per_ip() {
+	let fire = build_fire(|b| b.max_connections_per_ip(2)).await;
+	let addr = fire.local_addr().unwrap();
+	let pit = fire.pit();
+	tokio::task::spawn(fire.ignite());
+
+	let mut first = connect(addr).await;
+	let mut second = connect(addr).await;
+	assert_eq!(request(&mut first).await.unwrap(), 200);
+	assert_eq!(request(&mut second).await.unwrap(), 200);
+
+	let mut third = connect(addr).await;
+	assert!(request(&mut third).await.is_err());
+
+	assert_eq!(pit.connections_from(Ipv4Addr::LOCALHOST.into()), 2);
+	assert_eq!(pit.connections().rejected, 1);
+}
@@ -0,0 +1,226 @@
+use fire_http as fire;
+
+use fire::extractor::{PathDecoded, PathParam, PathStr};
+use fire::header::Method;
+use fire::resources::Resources;
+use fire::routes::{ParamsNames, PathParams, Route, RoutePath};
+use fire::util::PinnedFuture;
+use fire::{get, post, Request, Response};
+
+#[macro_use]
+mod util;
+
+#[get("/users/{id:u64}")]
+fn user_by_id(id: PathParam<u64>) -> String {
+	format!("id {id}")
+}
+
+#[get("/users/{id}")]
+fn user_by_name(id: &PathStr) -> String {
+	format!("name {id}")
+}
+
+#[get("/posts/{slug:[a-z-]+}")]
+fn post(slug: &PathStr) -> String {
+	slug.to_string()
+}
+
+#[get("/codes/{code:[0-9]{3}}")]
+fn get_code(code: PathParam<u16>) -> String {
+	code.to_string()
+}
+
+#[post("/codes/{code:[0-9]{3}}")]
+fn set_code(code: PathParam<u16>) -> String {
+	code.to_string()
+}
+
+#[get("/files/{*path}")]
+fn file(path: PathDecoded<String>) -> String {
+	path.into_inner()
+}
+
+#[tokio::test]
+async fn constraints() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(user_by_id);
+		builder.add_route(user_by_name);
+		builder.add_route(post);
+		builder.add_route(get_code);
+		builder.add_route(set_code);
+	});
+
+	make_request!("GET", addr, "/users/42")
+		.await
+		.assert_status(200)
+		.assert_body_str("id 42")
+		.await;
+
+	// falls through to the route without a constraint
+	make_request!("GET", addr, "/users/anna")
+		.await
+		.assert_status(200)
+		.assert_body_str("name anna")
+		.await;
+
+	// constraints check the value like PathParam parses it
+	make_request!("GET", addr, "/users/%34%32")
+		.await
+		.assert_status(200)
+		.assert_body_str("name %34%32")
+		.await;
+
+	make_request!("GET", addr, "/posts/hello-world")
+		.await
+		.assert_status(200)
+		.assert_body_str("hello-world")
+		.await;
+
+	make_request!("GET", addr, "/posts/Hello")
+		.await
+		.assert_status(404);
+
+	make_request!("GET", addr, "/codes/404")
+		.await
+		.assert_status(200)
+		.assert_body_str("404")
+		.await;
+
+	// no route exists for the path, even for another method
+	make_request!("PUT", addr, "/codes/4040")
+		.await
+		.assert_status(404);
+
+	make_request!("PUT", addr, "/codes/404")
+		.await
+		.assert_status(405)
+		.assert_header("allow", "GET, POST, HEAD, OPTIONS");
+}
+
+#[tokio::test]
+async fn decoded() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(file);
+	});
+
+	make_request!("GET", addr, "/files/docs/a%20b.txt")
+		.await
+		.assert_status(200)
+		.assert_body_str("docs/a b.txt")
+		.await;
+
+	make_request!("GET", addr, "/files/%FF")
+		.await
+		.assert_status(400);
+}
+
+/// The route macros already reject unknown constraints.
+struct User;
+
+impl Route for User {
+	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {}
+
+	fn path(&self) -> RoutePath {
+		RoutePath {
+			method: Some(Method::GET),
+			path: "/users/{id:uuid}".into(),
+		}
+	}
+
+	fn call<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		_params: &'a PathParams,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Response>> {
+		PinnedFuture::new(async { Ok(Response::builder().build()) })
+	}
+}
+
+#[tokio::test]
+async fn invalid_constraint() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	let err = builder.try_add_route(User).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"route GET /users/{id:uuid} (constraints::User) \
+		is invalid: unknown constraint \"uuid\""
+	);
+}
+
+#[get("/items/{id:u64}")]
+fn item_by_id(id: PathParam<u64>) -> String {
+	format!("id {id}")
+}
+
+#[get("/items/{name}")]
+fn item_by_name(name: &PathStr) -> String {
+	format!("name {name}")
+}
+
+#[get("/items/{slug}")]
+fn item_by_slug(slug: &PathStr) -> String {
+	format!("slug {slug}")
+}
+
+#[tokio::test]
+async fn different_names() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(item_by_id);
+		builder.add_route(item_by_name);
+	});
+
+	make_request!("GET", addr, "/items/42")
+		.await
+		.assert_status(200)
+		.assert_body_str("id 42")
+		.await;
+
+	make_request!("GET", addr, "/items/anna")
+		.await
+		.assert_status(200)
+		.assert_body_str("name anna")
+		.await;
+
+	// the names don't matter for a conflict
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_route(item_by_name);
+	let err = builder.try_add_route(item_by_slug).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"route GET /items/{slug} (constraints::item_by_slug) conflicts with \
+		GET /items/{name} (constraints::item_by_name)"
+	);
+}
+
+#[get("/{*rest}")]
+fn catch_all(rest: &PathStr) -> String {
+	format!("rest {rest}")
+}
+
+#[tokio::test]
+async fn other_template() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(user_by_id);
+		builder.add_route(catch_all);
+	});
+
+	make_request!("GET", addr, "/users/42")
+		.await
+		.assert_status(200)
+		.assert_body_str("id 42")
+		.await;
+
+	// matchit only returns /users/{id} which does not match
+	make_request!("GET", addr, "/users/abc")
+		.await
+		.assert_status(200)
+		.assert_body_str("rest users/abc")
+		.await;
+
+	make_request!("GET", addr, "/posts")
+		.await
+		.assert_status(200)
+		.assert_body_str("rest posts")
+		.await;
+}
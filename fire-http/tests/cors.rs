@@ -0,0 +1,237 @@
+use fire_http as fire;
+
+use fire::cors::Cors;
+use fire::header::{Method, StatusCode};
+use fire::resources::Resources;
+use fire::routes::Middleware;
+use fire::util::PinnedFuture;
+use fire::{get, post, Request, Response};
+
+use std::time::Duration;
+
+#[macro_use]
+mod util;
+
+#[get("/items")]
+fn items() -> &'static str {
+	"[]"
+}
+
+#[post("/items")]
+fn add_item() -> &'static str {
+	"ok"
+}
+
+macro_rules! preflight {
+	($addr:expr, $origin:expr, $method:expr, $headers:expr) => {
+		make_request!("OPTIONS", $addr, "/items", |builder| {
+			builder
+				.header("origin", $origin)
+				.header("access-control-request-method", $method)
+				.header("access-control-request-headers", $headers)
+				.body(fire::Body::new().into_http_body())
+				.unwrap()
+		})
+	};
+}
+
+macro_rules! with_origin {
+	($method:expr, $addr:expr, $origin:expr) => {
+		make_request!($method, $addr, "/items", |builder| {
+			builder
+				.header("origin", $origin)
+				.body(fire::Body::new().into_http_body())
+				.unwrap()
+		})
+	};
+}
+
+#[tokio::test]
+async fn preflight() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(
+			Cors::new()
+				.allow_origin("https://example.com")
+				.allow_methods([Method::GET, Method::POST, Method::DELETE])
+				.allow_headers(["Content-Type"])
+				.allow_credentials()
+				.max_age(Duration::from_secs(600)),
+		);
+		builder.add_route(items);
+		builder.add_route(add_item);
+	});
+
+	preflight!(addr, "https://example.com", "POST", "content-type")
+		.await
+		.assert_status(204)
+		.assert_header("access-control-allow-origin", "https://example.com")
+		.assert_header("access-control-allow-methods", "GET, POST")
+		.assert_header("access-control-allow-headers", "content-type")
+		.assert_header("access-control-allow-credentials", "true")
+		.assert_header("access-control-max-age", "600")
+		.assert_header(
+			"vary",
+			"origin, access-control-request-method, \
+			 access-control-request-headers",
+		);
+
+	// the origin is not allowed
+	preflight!(addr, "https://other.com", "POST", "content-type")
+		.await
+		.assert_not_header("access-control-allow-origin");
+
+	// the header is not allowed
+	preflight!(addr, "https://example.com", "POST", "x-custom")
+		.await
+		.assert_not_header("access-control-allow-origin");
+
+	// no route exists for the method
+	preflight!(addr, "https://example.com", "DELETE", "")
+		.await
+		.assert_not_header("access-control-allow-origin");
+
+	// the method is not allowed
+	preflight!(addr, "https://example.com", "HEAD", "")
+		.await
+		.assert_not_header("access-control-allow-origin");
+}
+
+#[tokio::test]
+async fn simple_requests() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(
+			Cors::new()
+				.allow_origin("https://example.com")
+				.expose_headers(["x-total"]),
+		);
+		builder.add_route(items);
+	});
+
+	with_origin!("GET", addr, "https://example.com")
+		.await
+		.assert_status(200)
+		.assert_header("access-control-allow-origin", "https://example.com")
+		.assert_header("access-control-expose-headers", "x-total")
+		.assert_not_header("access-control-allow-credentials")
+		.assert_header("vary", "origin")
+		.assert_body_str("[]")
+		.await;
+
+	with_origin!("GET", addr, "https://other.com")
+		.await
+		.assert_status(200)
+		.assert_not_header("access-control-allow-origin")
+		.assert_header("vary", "origin");
+}
+
+#[tokio::test]
+async fn any_origin() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Cors::new().allow_any_header());
+		builder.add_route(items);
+	});
+
+	with_origin!("GET", addr, "https://example.com")
+		.await
+		.assert_header("access-control-allow-origin", "*")
+		.assert_not_header("vary");
+
+	preflight!(addr, "https://example.com", "GET", "x-a, x-b")
+		.await
+		.assert_status(204)
+		.assert_header("access-control-allow-origin", "*")
+		.assert_header("access-control-allow-methods", "GET, HEAD, OPTIONS")
+		.assert_header("access-control-allow-headers", "x-a, x-b");
+
+	// requests without an origin are not cors requests
+	make_request!("GET", addr, "/items")
+		.await
+		.assert_not_header("access-control-allow-origin");
+}
+
+#[tokio::test]
+async fn fallback() {
+	// like a single page app which answers every path
+	#[get("/")]
+	fn app() -> &'static str {
+		"app"
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Cors::new());
+		builder.add_route(items);
+		builder.fallback(app);
+	});
+
+	// the fallback answers but no route exists
+	make_request!("OPTIONS", addr, "/unknown", |builder| {
+		builder
+			.header("origin", "https://example.com")
+			.header("access-control-request-method", "DELETE")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_not_header("access-control-allow-origin")
+	.assert_not_header("access-control-allow-methods");
+
+	preflight!(addr, "https://example.com", "DELETE", "")
+		.await
+		.assert_not_header("access-control-allow-origin");
+
+	preflight!(addr, "https://example.com", "GET", "")
+		.await
+		.assert_status(204)
+		.assert_header("access-control-allow-origin", "*")
+		.assert_header("access-control-allow-methods", "GET, HEAD, OPTIONS");
+}
+
+/// Rejects every request without an `authorization` header.
+struct Auth;
+
+impl Middleware for Auth {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			match req.header().value("authorization") {
+				Some(_) => Ok(None),
+				None => Ok(Some(StatusCode::UNAUTHORIZED.into())),
+			}
+		})
+	}
+}
+
+#[tokio::test]
+async fn rejecting_middleware() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Cors::new());
+		builder.add_middleware(Auth);
+		builder.add_route(items);
+		builder.add_route(add_item);
+	});
+
+	// preflights do not contain credentials
+	preflight!(addr, "https://example.com", "POST", "")
+		.await
+		.assert_status(204)
+		.assert_header("access-control-allow-origin", "*")
+		.assert_header(
+			"access-control-allow-methods",
+			"GET, POST, HEAD, OPTIONS",
+		);
+
+	// a preflight which is not allowed reaches the middleware
+	preflight!(addr, "https://example.com", "DELETE", "")
+		.await
+		.assert_status(401)
+		.assert_not_header("access-control-allow-origin");
+
+	with_origin!("POST", addr, "https://example.com")
+		.await
+		.assert_status(401)
+		.assert_header("access-control-allow-origin", "*");
+}
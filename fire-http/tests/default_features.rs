@@ -162,3 +162,253 @@ async fn anything() {
 		.assert_body_vec(&data)
 		.await;
 }
+
+#[tokio::test]
+async fn method_not_allowed() {
+	#[get("/item/{id}")]
+	fn get_item() -> &'static str {
+		"get"
+	}
+
+	#[post("/item/{id}")]
+	fn post_item() -> &'static str {
+		"post"
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(get_item);
+		builder.add_route(post_item);
+	});
+
+	make_request!("DELETE", addr, "/item/1")
+		.await
+		.assert_status(405)
+		.assert_header("allow", "GET, POST, HEAD, OPTIONS")
+		.assert_body_str("")
+		.await;
+
+	make_request!("POST", addr, "/item/1")
+		.await
+		.assert_status(200)
+		.assert_body_str("post")
+		.await;
+
+	make_request!("DELETE", addr, "/other")
+		.await
+		.assert_status(404)
+		.assert_not_header("allow");
+}
+
+#[tokio::test]
+async fn head_and_options() {
+	const BODY: &str = "Hello, World!";
+
+	#[get("/")]
+	fn hello() -> &'static str {
+		BODY
+	}
+
+	#[get("/explicit")]
+	fn explicit_get() -> &'static str {
+		BODY
+	}
+
+	#[fire::head("/explicit")]
+	fn explicit_head() -> Response {
+		Response::builder().header("x-explicit", "head").build()
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(hello);
+		builder.add_route(explicit_get);
+		builder.add_route(explicit_head);
+	});
+
+	make_request!("HEAD", addr, "/")
+		.await
+		.assert_status(200)
+		.assert_header("content-length", BODY.len().to_string())
+		.assert_body_str("")
+		.await;
+
+	make_request!("OPTIONS", addr, "/")
+		.await
+		.assert_status(204)
+		.assert_header("allow", "GET, HEAD, OPTIONS")
+		.assert_body_str("")
+		.await;
+
+	// an explicitly registered head route takes precedence
+	make_request!("HEAD", addr, "/explicit")
+		.await
+		.assert_status(200)
+		.assert_header("x-explicit", "head")
+		.assert_body_str("")
+		.await;
+
+	make_request!("OPTIONS", addr, "/explicit")
+		.await
+		.assert_status(204)
+		.assert_header("allow", "GET, HEAD, OPTIONS");
+
+	make_request!("OPTIONS", addr, "/unknown")
+		.await
+		.assert_status(404);
+}
+
+#[tokio::test]
+async fn route_infos() {
+	use fire::header::Method;
+	use fire::routes::RouteKind;
+
+	#[get("/")]
+	fn index() -> &'static str {
+		"index"
+	}
+
+	#[post("/{user}/posts/{*rest}")]
+	fn user_posts() -> &'static str {
+		"posts"
+	}
+
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_route(index);
+	builder.scope("/api", |api| api.add_route(user_posts));
+	let fire = builder.build().await.unwrap();
+
+	let routes: Vec<_> = fire
+		.pit()
+		.routes()
+		.iter()
+		.map(|r| (r.method.clone(), r.path.clone(), r.params.clone(), r.kind))
+		.collect();
+
+	assert_eq!(
+		routes,
+		[
+			(Some(Method::GET), "/".into(), vec![], RouteKind::Basic),
+			(
+				Some(Method::POST),
+				"/api/{user}/posts/{*rest}".into(),
+				vec!["user".into(), "rest".into()],
+				RouteKind::Basic
+			)
+		]
+	);
+}
+
+#[tokio::test]
+async fn extension_methods() {
+	#[fire::route("PROPFIND", "/dav/{*path}")]
+	fn propfind(path: &PathStr) -> String {
+		format!("propfind {path}")
+	}
+
+	#[fire::route("PURGE", "/dav/{*path}")]
+	fn purge() -> &'static str {
+		"purge"
+	}
+
+	#[get("/dav/{*path}")]
+	fn dav_get() -> &'static str {
+		"get"
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(propfind);
+		builder.add_route(purge);
+		builder.add_route(dav_get);
+	});
+
+	make_request!("PROPFIND", addr, "/dav/docs/a.txt")
+		.await
+		.assert_status(200)
+		.assert_body_str("propfind docs/a.txt")
+		.await;
+
+	make_request!("PURGE", addr, "/dav/docs/a.txt")
+		.await
+		.assert_status(200)
+		.assert_body_str("purge")
+		.await;
+
+	make_request!("GET", addr, "/dav/docs/a.txt")
+		.await
+		.assert_status(200)
+		.assert_body_str("get")
+		.await;
+
+	make_request!("MKCOL", addr, "/dav/docs")
+		.await
+		.assert_status(405)
+		.assert_header("allow", "GET, HEAD, OPTIONS, PROPFIND, PURGE");
+}
+
+#[tokio::test]
+async fn route_size_limit() {
+	#[post("/upload", size_limit = 1024, timeout = 5)]
+	async fn upload(req: &mut Request) -> fire::Result<String> {
+		let body = req
+			.take_body()
+			.into_string()
+			.await
+			.map_err(fire::Error::from_client_io)?;
+
+		Ok(body.len().to_string())
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.request_size_limit(16);
+		builder.add_route(upload);
+	});
+
+	make_request!("POST", addr, "/upload", "a".repeat(512))
+		.await
+		.assert_status(200)
+		.assert_body_str("512")
+		.await;
+
+	make_request!("POST", addr, "/upload", "a".repeat(2048))
+		.await
+		.assert_status(413);
+}
+
+#[tokio::test]
+async fn fallback() {
+	#[get("/")]
+	fn index() -> &'static str {
+		"index"
+	}
+
+	#[get("/")]
+	fn not_found(req: &mut Request) -> Response {
+		Response::builder()
+			.status_code(StatusCode::NOT_FOUND)
+			.content_type(Mime::TEXT)
+			.body(format!("{} not found", req.header().uri().path()))
+			.build()
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(index);
+		builder.fallback(not_found);
+	});
+
+	make_request!("GET", addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("index")
+		.await;
+
+	make_request!("POST", addr, "/missing")
+		.await
+		.assert_status(404)
+		.assert_body_str("/missing not found")
+		.await;
+
+	// the path exists for another method
+	make_request!("POST", addr, "/")
+		.await
+		.assert_status(405)
+		.assert_header("allow", "GET, HEAD, OPTIONS");
+}
@@ -0,0 +1,107 @@
+use fire_http as fire;
+
+use fire::forwarded::{ForwardedHeader, TrustedProxies};
+use fire::get;
+use fire::header::RequestHeader;
+
+#[macro_use]
+mod util;
+
+#[get("/path")]
+fn client(header: &RequestHeader) -> String {
+	let url = header.to_url().unwrap();
+	format!(
+		"{} {}://{}",
+		header.address().ip(),
+		url.scheme(),
+		url.host()
+	)
+}
+
+#[tokio::test]
+async fn trusted_proxy() {
+	let addr = spawn_server!(|builder| {
+		let cidr = "127.0.0.0/8".parse().unwrap();
+		builder.trusted_proxies(TrustedProxies::cidrs([cidr]));
+		builder.add_route(client);
+	});
+
+	make_request!("GET", addr, "/path", |builder| {
+		builder
+			.header("x-forwarded-for", "192.0.2.60, 127.0.0.2")
+			.header("x-forwarded-proto", "https")
+			.header("x-forwarded-host", "example.com")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_body_str("192.0.2.60 https://example.com")
+	.await;
+}
+
+#[tokio::test]
+async fn forwarded_header() {
+	let addr = spawn_server!(|builder| {
+		let cidr = "127.0.0.0/8".parse().unwrap();
+		let proxies =
+			TrustedProxies::cidrs([cidr]).header(ForwardedHeader::Forwarded);
+		builder.trusted_proxies(proxies);
+		builder.add_route(client);
+	});
+
+	make_request!("GET", addr, "/path", |builder| {
+		builder
+			.header("forwarded", "for=\"[2001:db8::1]:80\";proto=http")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_body_str("2001:db8::1 http://127.0.0.1")
+	.await;
+}
+
+/// A proxy which only appends `X-Forwarded-For` passes the `Forwarded`
+/// header of the client on.
+#[tokio::test]
+async fn spoofed_forwarded_header() {
+	let addr = spawn_server!(|builder| {
+		let cidr = "127.0.0.1".parse().unwrap();
+		builder.trusted_proxies(TrustedProxies::cidrs([cidr]));
+		builder.add_route(client);
+	});
+
+	make_request!("GET", addr, "/path", |builder| {
+		builder
+			.header("x-forwarded-for", "203.0.113.9")
+			.header("forwarded", "for=1.2.3.4;host=evil.example")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_body_str("203.0.113.9 http://127.0.0.1")
+	.await;
+}
+
+#[tokio::test]
+async fn untrusted_proxy() {
+	let addr = spawn_server!(|builder| {
+		let cidr = "10.0.0.0/8".parse().unwrap();
+		builder.trusted_proxies(TrustedProxies::cidrs([cidr]));
+		builder.add_route(client);
+	});
+
+	make_request!("GET", addr, "/path", |builder| {
+		builder
+			.header("x-forwarded-for", "192.0.2.60")
+			.header("x-forwarded-proto", "https")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_body_str("127.0.0.1 http://127.0.0.1")
+	.await;
+}
@@ -43,3 +43,28 @@ async fn read_file() {
 	.assert_header("cache-control", "max-age=86400, public")
 	.assert_not_header("content-type");
 }
+
+#[tokio::test]
+async fn trailing_slash() {
+	use fire::routes::TrailingSlash;
+
+	const CSS: StaticFiles = StaticFiles::new("/css", "./examples/www/css");
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(CSS);
+	});
+
+	make_request!("GET", addr, "/css/style.css/")
+		.await
+		.assert_status(404);
+
+	let addr = spawn_server!(|builder| {
+		builder.trailing_slash(TrailingSlash::MovedPermanently);
+		builder.add_route(CSS);
+	});
+
+	make_request!("GET", addr, "/css/style.css/")
+		.await
+		.assert_status(301)
+		.assert_header("location", "/css/style.css");
+}
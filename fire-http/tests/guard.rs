@@ -0,0 +1,144 @@
+use fire_http as fire;
+
+use fire::header::Mime;
+use fire::routes::{Guard, Guarded};
+use fire::{get, post};
+
+#[macro_use]
+mod util;
+
+macro_rules! request_with {
+	($method:expr, $addr:expr, $uri:expr, $($key:expr => $value:expr),*) => {
+		make_request!($method, $addr, $uri, |builder| {
+			builder
+				$(.header($key, $value))*
+				.body(fire::Body::new().into_http_body())
+				.expect("could not build request")
+		})
+	};
+}
+
+#[post("/items")]
+fn create_json() -> &'static str {
+	"json"
+}
+
+#[post("/items")]
+fn create_xml() -> &'static str {
+	"xml"
+}
+
+#[get("/items")]
+fn list_v2() -> &'static str {
+	"v2"
+}
+
+#[get("/items")]
+fn list_html() -> &'static str {
+	"html"
+}
+
+#[get("/search")]
+fn search() -> &'static str {
+	"search"
+}
+
+#[get("/search")]
+fn search_form() -> &'static str {
+	"form"
+}
+
+#[tokio::test]
+async fn guards() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(
+			Guarded::new(create_json).guard(Guard::content_type(Mime::JSON)),
+		);
+		builder.add_route(
+			Guarded::new(create_xml).guard(Guard::content_type(Mime::XML)),
+		);
+		builder.add_route(
+			Guarded::new(list_v2).guard(Guard::header("x-api-version", "2")),
+		);
+		builder.add_route(
+			Guarded::new(list_html).guard(Guard::accept(Mime::HTML)),
+		);
+		builder.add_route(Guarded::new(search).guard(Guard::query("q")));
+		// a route without guards is used if every other one fails
+		builder.add_route(search_form);
+	});
+
+	request_with!(
+		"POST", addr, "/items",
+		"content-type" => "application/json; charset=utf-8"
+	)
+	.await
+	.assert_status(200)
+	.assert_body_str("json")
+	.await;
+
+	request_with!("POST", addr, "/items", "content-type" => "text/xml")
+		.await
+		.assert_status(200)
+		.assert_body_str("xml")
+		.await;
+
+	request_with!("POST", addr, "/items", "content-type" => "text/plain")
+		.await
+		.assert_status(415);
+
+	// routes are tried in the order they were added
+	request_with!(
+		"GET", addr, "/items",
+		"x-api-version" => "2",
+		"accept" => "text/html"
+	)
+	.await
+	.assert_status(200)
+	.assert_body_str("v2")
+	.await;
+
+	request_with!("GET", addr, "/items", "accept" => "text/html, */*;q=0.8")
+		.await
+		.assert_status(200)
+		.assert_body_str("html")
+		.await;
+
+	request_with!("GET", addr, "/items", "accept" => "application/json")
+		.await
+		.assert_status(406);
+
+	make_request!("GET", addr, "/search?q=fire")
+		.await
+		.assert_status(200)
+		.assert_body_str("search")
+		.await;
+
+	make_request!("GET", addr, "/search")
+		.await
+		.assert_status(200)
+		.assert_body_str("form")
+		.await;
+}
+
+#[tokio::test]
+async fn guard_not_found() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(
+			Guarded::new(list_v2)
+				.guard(Guard::header_matches("x-api-version", |v| {
+					v.parse::<u8>().is_ok_and(|v| v >= 2)
+				})),
+		);
+	});
+
+	request_with!("GET", addr, "/items", "x-api-version" => "3")
+		.await
+		.assert_status(200)
+		.assert_body_str("v2")
+		.await;
+
+	request_with!("GET", addr, "/items", "x-api-version" => "1")
+		.await
+		.assert_status(404);
+}
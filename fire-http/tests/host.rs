@@ -0,0 +1,82 @@
+use fire_http as fire;
+
+use fire::extractor::PathStr;
+use fire::get;
+
+#[macro_use]
+mod util;
+
+macro_rules! request_host {
+	($addr:expr, $host:expr, $uri:expr) => {
+		make_request!("GET", $addr, $uri, |builder| {
+			let mut builder = builder;
+			builder
+				.headers_mut()
+				.unwrap()
+				.insert("host", $host.parse().unwrap());
+			builder
+				.body(fire::Body::new().into_http_body())
+				.expect("could not build request")
+		})
+	};
+}
+
+#[get("/")]
+fn index() -> &'static str {
+	"index"
+}
+
+#[get("/")]
+fn admin() -> &'static str {
+	"admin"
+}
+
+#[get("/{page}")]
+async fn tenant_page(tenant: &PathStr, page: &PathStr) -> String {
+	format!("{tenant}: {page}")
+}
+
+#[tokio::test]
+async fn virtual_hosts() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(index);
+		builder.host("admin.example.com", |host| host.add_route(admin));
+		builder.host("{tenant}.example.com", |host| {
+			host.scope("/pages", |pages| pages.add_route(tenant_page));
+		});
+	});
+
+	request_host!(addr, "example.com", "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("index")
+		.await;
+
+	// exact hosts are preferred, the port gets ignored
+	request_host!(addr, "Admin.example.com:8080", "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("admin")
+		.await;
+
+	request_host!(addr, "acme.example.com", "/pages/about")
+		.await
+		.assert_status(200)
+		.assert_body_str("acme: about")
+		.await;
+
+	// routes which are not bound to a host match every host
+	request_host!(addr, "acme.example.com", "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("index")
+		.await;
+
+	request_host!(addr, "example.com", "/pages/about")
+		.await
+		.assert_status(404);
+
+	request_host!(addr, "a.b.example.com", "/pages/about")
+		.await
+		.assert_status(404);
+}
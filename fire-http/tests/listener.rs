@@ -0,0 +1,144 @@
+use fire_http as fire;
+
+use fire::header::{PeerAddr, RequestHeader};
+use fire::listener::Listener;
+use fire::{get, Body};
+
+#[macro_use]
+mod util;
+
+#[get("/")]
+fn peer(header: &RequestHeader) -> String {
+	match header.peer() {
+		PeerAddr::Tcp(addr) => {
+			assert_eq!(addr, header.address());
+			"tcp".into()
+		}
+		PeerAddr::Unix(_) => {
+			assert!(header.address().ip().is_unspecified());
+			"unix".into()
+		}
+	}
+}
+
+#[tokio::test]
+async fn std_tcp_listener() {
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+
+	let mut builder =
+		fire::build_with_listener(Listener::from_std(listener).unwrap());
+	builder.add_route(peer);
+	let fire = builder.build().await.unwrap();
+	assert_eq!(fire.local_addr(), Some(addr));
+	tokio::task::spawn(fire.ignite());
+
+	make_request!("GET", addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("tcp")
+		.await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_listener() {
+	use hyper_util::rt::TokioIo;
+	use tokio::net::UnixStream;
+
+	let path = std::env::temp_dir()
+		.join(format!("fire-http-test-{}.sock", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+
+	let mut builder =
+		fire::build_with_listener(Listener::bind_unix(&path).unwrap());
+	builder.add_route(peer);
+	let fire = builder.build().await.unwrap();
+	assert_eq!(fire.local_addr(), None);
+	assert_eq!(fire.listener().local_path().as_ref(), Some(&path));
+	tokio::task::spawn(fire.ignite());
+
+	let stream = UnixStream::connect(&path).await.unwrap();
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake(TokioIo::new(stream))
+			.await
+			.unwrap();
+	tokio::task::spawn(conn);
+
+	let req = hyper::Request::builder()
+		.uri("/")
+		.header("host", "localhost")
+		.body(Body::new().into_http_body())
+		.unwrap();
+
+	let resp = sender.send_request(req).await.unwrap();
+	assert_eq!(resp.status(), 200);
+
+	let body = Body::from_hyper(resp.into_body())
+		.into_string()
+		.await
+		.unwrap();
+	assert_eq!(body, "unix");
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn multiple_listeners() {
+	#[get("/")]
+	fn listener(header: &RequestHeader) -> String {
+		header.listener().unwrap().to_string()
+	}
+
+	let public = Listener::bind("127.0.0.1:0").await.unwrap();
+	let admin = Listener::bind("127.0.0.1:0")
+		.await
+		.unwrap()
+		.with_name("admin");
+	let public_addr = public.local_addr().unwrap();
+	let admin_addr = admin.local_addr().unwrap();
+
+	let mut builder = fire::build_with_listener(public);
+	builder.add_listener(admin);
+	builder.add_route(listener);
+	let fire = builder.build().await.unwrap();
+	assert_eq!(fire.local_addr(), Some(public_addr));
+	let names: Vec<_> = fire.listeners().map(|l| l.name()).collect();
+	assert_eq!(names, ["default", "admin"]);
+	tokio::task::spawn(fire.ignite());
+
+	make_request!("GET", public_addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("default")
+		.await;
+
+	make_request!("GET", admin_addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("admin")
+		.await;
+}
+
+#[tokio::test]
+async fn named_address() {
+	#[get("/")]
+	fn listener(header: &RequestHeader) -> String {
+		header.listener().unwrap().to_string()
+	}
+
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_address("admin", "127.0.0.1:0").await.unwrap();
+	builder.add_route(listener);
+	let fire = builder.build().await.unwrap();
+	let names: Vec<_> = fire.listeners().map(|l| l.name()).collect();
+	assert_eq!(names, ["default", "admin"]);
+	let admin_addr = fire.listeners().nth(1).unwrap().local_addr().unwrap();
+	tokio::task::spawn(fire.ignite());
+
+	make_request!("GET", admin_addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("admin")
+		.await;
+}
@@ -0,0 +1,229 @@
+use fire_http as fire;
+
+use fire::error::ClientErrorKind;
+use fire::extractor::Ext;
+use fire::header::StatusCode;
+use fire::resources::Resources;
+use fire::routes::Middleware;
+use fire::util::PinnedFuture;
+use fire::{get, post, Error, Request, Response};
+
+#[macro_use]
+mod util;
+
+struct User(String);
+
+/// Rejects requests without an `x-user` header.
+struct Auth;
+
+impl Middleware for Auth {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			let Some(user) = req.header().value("x-user") else {
+				return Ok(Some(StatusCode::UNAUTHORIZED.into()));
+			};
+			if user == "banned" {
+				return Err(Error::empty(ClientErrorKind::Forbidden));
+			}
+
+			let user = User(user.to_string());
+			req.header.extensions.insert(user);
+			Ok(None)
+		})
+	}
+}
+
+#[derive(Clone, Default)]
+struct Trace(Vec<&'static str>);
+
+/// Records the order of `before` in the request and of `after` in the
+/// response.
+struct Tag(&'static str);
+
+impl Middleware for Tag {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			let extensions = &mut req.header.extensions;
+			let mut trace =
+				extensions.get::<Trace>().cloned().unwrap_or_default();
+			trace.0.push(self.0);
+			extensions.insert(trace);
+			Ok(None)
+		})
+	}
+
+	fn after<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<()>> {
+		PinnedFuture::new(async move {
+			let tags = match resp.header().value("x-tags") {
+				Some(tags) => format!("{tags},{}", self.0),
+				None => self.0.to_string(),
+			};
+			resp.header.values.insert("x-tags", tags);
+			Ok(())
+		})
+	}
+}
+
+#[get("/me")]
+fn me(user: Ext<User>) -> String {
+	user.0.clone()
+}
+
+#[get("/trace")]
+fn get_trace(trace: Ext<Trace>) -> String {
+	trace.0.join(",")
+}
+
+#[get("/api/trace")]
+fn get_api_trace(trace: Ext<Trace>) -> String {
+	trace.0.join(",")
+}
+
+#[tokio::test]
+async fn short_circuit() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Auth);
+		builder.add_route(me);
+	});
+
+	make_request!("GET", addr, "/me").await.assert_status(401);
+
+	make_request!("GET", addr, "/me", |builder| {
+		builder
+			.header("x-user", "banned")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(403);
+
+	make_request!("GET", addr, "/me", |builder| {
+		builder
+			.header("x-user", "alice")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(200)
+	.assert_body_str("alice")
+	.await;
+}
+
+#[tokio::test]
+async fn missing_extension() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(me);
+	});
+
+	make_request!("GET", addr, "/me").await.assert_status(500);
+}
+
+#[tokio::test]
+async fn ordering_and_scopes() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Tag("a"));
+		builder.add_middleware(Tag("b"));
+		builder.add_route(get_trace);
+		builder.scope("/api", |api| {
+			api.add_middleware(Tag("api"));
+			api.scope("/v1", |v1| v1.add_middleware(Tag("v1")));
+		});
+		builder.add_route(get_api_trace);
+	});
+
+	make_request!("GET", addr, "/trace")
+		.await
+		.assert_status(200)
+		.assert_header("x-tags", "b,a")
+		.assert_body_str("a,b")
+		.await;
+
+	make_request!("GET", addr, "/api/trace")
+		.await
+		.assert_status(200)
+		.assert_header("x-tags", "api,b,a")
+		.assert_body_str("a,b,api")
+		.await;
+
+	// middleware runs even if no route matches
+	make_request!("GET", addr, "/api/v1/unknown")
+		.await
+		.assert_status(404)
+		.assert_header("x-tags", "v1,api,b,a");
+}
+
+#[tokio::test]
+async fn after_skipped_on_short_circuit() {
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Tag("outer"));
+		builder.add_middleware(Auth);
+		builder.add_middleware(Tag("inner"));
+		builder.add_route(me);
+	});
+
+	make_request!("GET", addr, "/me")
+		.await
+		.assert_status(401)
+		.assert_header("x-tags", "outer");
+}
+
+/// Reads the body in `before` and stores its length.
+struct BodyLen;
+
+struct Len(usize);
+
+impl Middleware for BodyLen {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			let body = req
+				.take_body()
+				.into_bytes()
+				.await
+				.map_err(Error::from_client_io)?;
+			req.header.extensions.insert(Len(body.len()));
+			Ok(None)
+		})
+	}
+}
+
+#[post("/upload", size_limit = 1024)]
+fn upload(len: Ext<'_, Len>) -> String {
+	len.0.to_string()
+}
+
+#[tokio::test]
+async fn route_limits() {
+	let addr = spawn_server!(|builder| {
+		builder.request_size_limit(16);
+		builder.add_middleware(BodyLen);
+		builder.add_route(upload);
+	});
+
+	// the limit of the route applies to the middleware
+	make_request!("POST", addr, "/upload", "a".repeat(512))
+		.await
+		.assert_status(200)
+		.assert_body_str("512")
+		.await;
+
+	make_request!("POST", addr, "/upload", "a".repeat(2048))
+		.await
+		.assert_status(413);
+}
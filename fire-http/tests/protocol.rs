@@ -0,0 +1,78 @@
+use fire_http as fire;
+
+use fire::get;
+use fire::protocol::{Http1Config, ProtocolConfig};
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+#[macro_use]
+mod util;
+
+#[get("/")]
+fn hello() -> &'static str {
+	"hello"
+}
+
+#[tokio::test]
+async fn http1_settings() {
+	let http1 = Http1Config::new()
+		.header_read_timeout(Duration::from_millis(100))
+		.max_headers(4);
+
+	let addr = spawn_server!(|builder| {
+		builder
+			.protocol(ProtocolConfig::new().http1(http1))
+			.unwrap();
+		builder.add_route(hello);
+	});
+
+	make_request!("GET", addr, "/")
+		.await
+		.assert_status(200)
+		.assert_body_str("hello")
+		.await;
+
+	// too many headers
+	make_request!("GET", addr, "/", |builder| {
+		builder
+			.header("a", "1")
+			.header("b", "2")
+			.header("c", "3")
+			.header("d", "4")
+			.body(fire::Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(431);
+
+	// a client which never finishes sending the headers
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	stream.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+
+	let mut buf = vec![];
+	timeout(Duration::from_secs(2), stream.read_to_end(&mut buf))
+		.await
+		.expect("connection was not closed")
+		.unwrap();
+}
+
+#[cfg(not(feature = "http2"))]
+#[tokio::test]
+async fn disabled_protocol() {
+	use fire::protocol::Http2Config;
+	use std::net::{Ipv4Addr, SocketAddr};
+
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+
+	let http2 = Http2Config::new().max_concurrent_streams(10);
+	let err = builder
+		.protocol(ProtocolConfig::new().http2(http2))
+		.unwrap_err();
+
+	assert!(err.to_string().contains("max_concurrent_streams"));
+}
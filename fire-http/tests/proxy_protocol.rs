@@ -0,0 +1,136 @@
+use fire_http as fire;
+
+use fire::connections::Overflow;
+use fire::get;
+use fire::header::RequestHeader;
+use fire::listener::Listener;
+use fire::proxy_protocol::ProxyProtocol;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+#[get("/")]
+fn address(header: &RequestHeader) -> String {
+	header.address().to_string()
+}
+
+async fn spawn_server(mode: ProxyProtocol) -> SocketAddr {
+	let listener = Listener::bind("127.0.0.1:0")
+		.await
+		.unwrap()
+		.with_proxy_protocol(mode);
+
+	let mut builder = fire::build_with_listener(listener);
+	builder.add_route(address);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	tokio::task::spawn(fire.ignite());
+
+	addr
+}
+
+/// Sends the raw bytes followed by a request and returns the raw response.
+async fn send(addr: SocketAddr, header: &[u8]) -> String {
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	stream.write_all(header).await.unwrap();
+	stream
+		.write_all(
+			b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n",
+		)
+		.await
+		.unwrap();
+
+	let mut resp = vec![];
+	timeout(Duration::from_secs(2), stream.read_to_end(&mut resp))
+		.await
+		.unwrap()
+		.unwrap();
+
+	String::from_utf8(resp).unwrap()
+}
+
+#[tokio::test]
+async fn required() {
+	let addr = spawn_server(ProxyProtocol::Required).await;
+
+	let resp =
+		send(addr, b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n").await;
+	assert!(resp.starts_with("HTTP/1.1 200"));
+	assert!(resp.ends_with("192.0.2.1:56324"));
+
+	let mut v2 = b"\r\n\r\n\0\r\nQUIT\n\x21\x21\x00\x24".to_vec();
+	// 2001:db8::1 -> 2001:db8::2
+	v2.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+	v2.extend_from_slice(&[0; 11]);
+	v2.push(1);
+	v2.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+	v2.extend_from_slice(&[0; 11]);
+	v2.push(2);
+	v2.extend_from_slice(&[0xdc, 0x04, 0x01, 0xbb]);
+
+	let resp = send(addr, &v2).await;
+	assert!(resp.starts_with("HTTP/1.1 200"));
+	assert!(resp.ends_with("[2001:db8::1]:56324"));
+
+	// missing and malformed headers close the connection
+	assert_eq!(send(addr, b"").await, "");
+	assert_eq!(send(addr, b"PROXY TCP4 192.0.2.1\r\n").await, "");
+}
+
+#[tokio::test]
+async fn optional() {
+	let addr = spawn_server(ProxyProtocol::Optional).await;
+
+	let resp =
+		send(addr, b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n").await;
+	assert!(resp.ends_with("192.0.2.1:56324"));
+
+	let resp = send(addr, b"").await;
+	assert!(resp.starts_with("HTTP/1.1 200"));
+	assert!(resp.contains("\r\n\r\n127.0.0.1:"));
+}
+
+#[tokio::test]
+async fn idle_connections_count_towards_limit() {
+	let listener = Listener::bind("127.0.0.1:0")
+		.await
+		.unwrap()
+		.with_proxy_protocol(ProxyProtocol::Required);
+
+	let mut builder = fire::build_with_listener(listener);
+	builder.add_route(address);
+	builder.max_connections(2);
+	builder.connection_overflow(Overflow::Reject);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	let pit = fire.pit();
+	tokio::task::spawn(fire.ignite());
+
+	// connections which never send a header
+	let mut idle = vec![];
+	for _ in 0..2 {
+		idle.push(TcpStream::connect(addr).await.unwrap());
+	}
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	assert_eq!(pit.connections().current, 2);
+
+	// the third connection gets closed without waiting for a header
+	let mut third = TcpStream::connect(addr).await.unwrap();
+	let mut buf = vec![];
+	let read = timeout(Duration::from_secs(2), third.read_to_end(&mut buf))
+		.await
+		.expect("connection was not closed");
+	assert!(read.map_or(true, |n| n == 0));
+	assert_eq!(pit.connections().rejected, 1);
+
+	// once an idle connection closes, a new one is served
+	drop(idle.pop());
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let resp =
+		send(addr, b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n").await;
+	assert!(resp.ends_with("192.0.2.1:56324"), "{resp}");
+}
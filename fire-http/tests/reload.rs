@@ -0,0 +1,105 @@
+use fire_http as fire;
+
+use fire::routes::RouteError;
+use fire::{get, Res};
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+#[macro_use]
+mod util;
+
+struct Version(&'static str);
+
+#[get("/version")]
+async fn get_version(version: Res<'_, Version>) -> &'static str {
+	// gives the reload time to happen while the request is running
+	sleep(Duration::from_millis(100)).await;
+	version.0
+}
+
+#[get("/old")]
+fn old() -> &'static str {
+	"old"
+}
+
+#[tokio::test]
+async fn reload_routes_and_resources() {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_data(Version("1"));
+	builder.add_route(get_version);
+	builder.add_route(old);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.reload_handle();
+	let pit = fire.pit();
+	tokio::task::spawn(fire.ignite());
+
+	make_request!("GET", addr, "/old")
+		.await
+		.assert_status(200)
+		.assert_body_str("old")
+		.await;
+
+	// an in-flight request keeps the old routes and resources
+	let in_flight = tokio::task::spawn(async move {
+		make_request!("GET", addr, "/version")
+			.await
+			.assert_status(200)
+			.assert_body_str("1")
+			.await;
+	});
+	sleep(Duration::from_millis(20)).await;
+
+	let mut builder = handle.builder();
+	builder.add_data(Version("2"));
+	builder.add_route(get_version);
+	handle.reload(builder).unwrap();
+
+	make_request!("GET", addr, "/version")
+		.await
+		.assert_status(200)
+		.assert_body_str("2")
+		.await;
+
+	make_request!("GET", addr, "/old").await.assert_status(404);
+
+	in_flight.await.unwrap();
+
+	// the pit was created before the reload
+	assert_eq!(pit.data().get::<Version>().unwrap().0, "1");
+	assert_eq!(handle.builder().data().get::<Version>().map(|v| v.0), None);
+}
+
+#[get("/old")]
+fn other_old() -> &'static str {
+	"other"
+}
+
+#[tokio::test]
+async fn failed_reload_keeps_routes() {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_route(old);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.reload_handle();
+	tokio::task::spawn(fire.ignite());
+
+	let mut builder = handle.builder();
+	builder.collect_route_errors();
+	builder.add_route(old);
+	builder.add_route(other_old);
+	let errors = handle.reload(builder).unwrap_err().0;
+	assert_eq!(errors.len(), 1);
+	assert!(matches!(errors[0], RouteError::Conflict { .. }));
+
+	make_request!("GET", addr, "/old")
+		.await
+		.assert_status(200)
+		.assert_body_str("old")
+		.await;
+}
@@ -0,0 +1,160 @@
+use fire_http as fire;
+
+use fire::get;
+use fire::header::Method;
+use fire::resources::Resources;
+use fire::routes::{ParamsNames, PathParams, Route, RouteError, RoutePath};
+use fire::util::PinnedFuture;
+use fire::{Request, Response};
+
+#[derive(Debug)]
+struct Missing;
+
+fire::impl_res_extractor!(Missing);
+
+#[get("/users/{id}")]
+fn user() -> &'static str {
+	"user"
+}
+
+#[get("/users/{name}")]
+fn user_by_name() -> &'static str {
+	"user"
+}
+
+#[get("/")]
+fn index() -> &'static str {
+	"index"
+}
+
+#[get("/")]
+fn other_index() -> &'static str {
+	"other"
+}
+
+#[get("/missing")]
+fn missing(_m: &Missing) -> &'static str {
+	"missing"
+}
+
+/// Reports its requirements without panicking.
+struct Fallible(&'static str);
+
+impl Route for Fallible {
+	fn validate_requirements(&self, _params: &ParamsNames, _data: &Resources) {
+		unreachable!("try_validate_requirements is implemented")
+	}
+
+	fn try_validate_requirements(
+		&self,
+		params: &ParamsNames,
+		_data: &Resources,
+	) -> Result<(), String> {
+		if params.exists("id") {
+			Ok(())
+		} else {
+			Err("param id is missing".into())
+		}
+	}
+
+	fn path(&self) -> RoutePath {
+		RoutePath {
+			method: Some(Method::GET),
+			path: self.0.into(),
+		}
+	}
+
+	fn call<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		_params: &'a PathParams,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Response>> {
+		PinnedFuture::new(async { Ok(Response::builder().build()) })
+	}
+}
+
+#[tokio::test]
+async fn conflicts() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.try_add_route(index).unwrap();
+	builder.try_add_route(user).unwrap();
+
+	let err = builder.try_add_route(other_index).unwrap_err();
+	let RouteError::Conflict { route, existing } = err else {
+		panic!("expected a conflict, got {err}");
+	};
+	assert_eq!(route.handler, "route_errors::other_index");
+	assert_eq!(existing.handler, "route_errors::index");
+	assert_eq!(existing.method, Some(Method::GET));
+
+	let err = builder.try_add_route(user_by_name).unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"route GET /users/{name} (route_errors::user_by_name) conflicts with \
+		GET /users/{id} (route_errors::user)"
+	);
+
+	// the routes were not added
+	assert_eq!(builder.build().await.unwrap().pit().routes().len(), 2);
+}
+
+#[tokio::test]
+async fn collect_errors() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.collect_route_errors();
+	builder.add_route(index);
+	builder.add_route(other_index);
+	builder.scope("/api", |api| {
+		api.add_route(user);
+		api.add_route(user_by_name);
+		api.add_route(missing);
+	});
+
+	let errors = builder.check_routes().unwrap_err().0;
+	assert_eq!(errors.len(), 3);
+	assert!(matches!(errors[0], RouteError::Conflict { .. }));
+	assert!(matches!(errors[1], RouteError::Conflict { .. }));
+	let RouteError::Requirements { route, message } = &errors[2] else {
+		panic!("expected requirements, got {}", errors[2]);
+	};
+	assert_eq!(route.path, "/api/missing");
+	assert_eq!(message, "Resource Missing does not exist");
+
+	// errors which were not checked make build fail
+	builder.add_route(missing);
+	assert!(builder.build().await.is_err());
+}
+
+#[tokio::test]
+async fn fallible_requirements() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+
+	let err = builder.try_add_route(Fallible("/fallible")).unwrap_err();
+	let RouteError::Requirements { route, message } = err else {
+		panic!("expected requirements, got {err}");
+	};
+	assert_eq!(route.path, "/fallible");
+	assert_eq!(message, "param id is missing");
+}
+
+#[tokio::test]
+async fn invalid_path() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+
+	for path in ["/bad/{id", "/bad/{id:unknown}"] {
+		let err = builder.try_add_route(Fallible(path)).unwrap_err();
+		let RouteError::InvalidPath { route, .. } = err else {
+			panic!("expected an invalid path, got {err}");
+		};
+		assert_eq!(route.path, path);
+	}
+}
+
+#[tokio::test]
+#[should_panic(expected = "conflicts with GET / (route_errors::index)")]
+async fn conflict_panics() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_route(index);
+	builder.add_route(other_index);
+}
@@ -0,0 +1,129 @@
+use fire_http as fire;
+
+use fire::header::{RequestHeader, ResponseHeader};
+use fire::resources::Resources;
+use fire::routes::Catcher;
+use fire::util::PinnedFuture;
+use fire::{get, post, Error, Request, Response};
+
+#[macro_use]
+mod util;
+
+struct Tag(&'static str);
+
+impl Catcher for Tag {
+	fn check(&self, _req: &RequestHeader, _res: &ResponseHeader) -> bool {
+		true
+	}
+
+	fn call<'a>(
+		&'a self,
+		_req: &'a mut Request,
+		resp: &'a mut Response,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<()>> {
+		PinnedFuture::new(async move {
+			let tags = match resp.header().value("x-tags") {
+				Some(tags) => format!("{tags},{}", self.0),
+				None => self.0.to_string(),
+			};
+			resp.header.values.insert("x-tags", tags);
+
+			Ok(())
+		})
+	}
+}
+
+#[get("/")]
+fn index() -> &'static str {
+	"index"
+}
+
+#[get("/users")]
+fn users() -> &'static str {
+	"users"
+}
+
+#[post("/upload")]
+async fn upload(req: &mut Request) -> fire::Result<String> {
+	let body = req
+		.take_body()
+		.into_string()
+		.await
+		.map_err(Error::from_client_io)?;
+
+	Ok(body.len().to_string())
+}
+
+#[tokio::test]
+async fn prefix_and_catchers() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(users);
+		builder.add_catcher(Tag("global"));
+		builder.scope("/api", |api| {
+			api.add_route(index);
+			api.add_catcher(Tag("api"));
+			api.scope("/v1", |v1| {
+				v1.add_route(users);
+				v1.add_catcher(Tag("v1"));
+			});
+		});
+	});
+
+	make_request!("GET", addr, "/users")
+		.await
+		.assert_status(200)
+		.assert_header("x-tags", "global")
+		.assert_body_str("users")
+		.await;
+
+	make_request!("GET", addr, "/api")
+		.await
+		.assert_status(200)
+		.assert_header("x-tags", "api,global")
+		.assert_body_str("index")
+		.await;
+
+	make_request!("GET", addr, "/api/v1/users")
+		.await
+		.assert_status(200)
+		.assert_header("x-tags", "v1,api,global")
+		.assert_body_str("users")
+		.await;
+
+	// catchers of a scope also get called if no route matches
+	make_request!("GET", addr, "/api/unknown")
+		.await
+		.assert_status(404)
+		.assert_header("x-tags", "api,global");
+
+	// the prefix only matches whole segments
+	make_request!("GET", addr, "/apiv1/users")
+		.await
+		.assert_status(404)
+		.assert_header("x-tags", "global");
+}
+
+#[tokio::test]
+async fn request_size_limit() {
+	let addr = spawn_server!(|builder| {
+		builder.request_size_limit(16);
+		builder.add_route(upload);
+		builder.scope("/admin", |admin| {
+			admin.request_size_limit(1024);
+			admin.add_route(upload);
+		});
+	});
+
+	let body = "a".repeat(512);
+
+	make_request!("POST", addr, "/upload", body.clone())
+		.await
+		.assert_status(413);
+
+	make_request!("POST", addr, "/admin/upload", body)
+		.await
+		.assert_status(200)
+		.assert_body_str("512")
+		.await;
+}
@@ -0,0 +1,80 @@
+use fire_http as fire;
+
+use fire::get;
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+#[macro_use]
+mod util;
+
+#[get("/slow/{ms}")]
+async fn slow(ms: fire::extractor::PathParam<u64>) -> &'static str {
+	sleep(Duration::from_millis(*ms)).await;
+	"done"
+}
+
+async fn build_fire(timeout: Duration) -> fire::Fire {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_route(slow);
+	builder.shutdown_timeout(timeout);
+	builder.build().await.unwrap()
+}
+
+#[tokio::test]
+async fn drains_in_flight_requests() {
+	let fire = build_fire(Duration::from_secs(5)).await;
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.shutdown_handle();
+	let server =
+		tokio::task::spawn(fire.ignite_with_shutdown(std::future::pending()));
+
+	let req = tokio::task::spawn(async move {
+		make_request!("GET", addr, "/slow/200")
+			.await
+			.assert_status(200)
+			.assert_body_str("done")
+			.await;
+	});
+
+	// wait until the request reached the server
+	sleep(Duration::from_millis(50)).await;
+	handle.shutdown();
+	assert!(handle.is_shutdown());
+
+	req.await.unwrap();
+	let report = server.await.unwrap().unwrap();
+	assert_eq!(report.drained, 1);
+	assert_eq!(report.force_closed, 0);
+
+	// the listener is closed
+	assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn force_closes_after_timeout() {
+	let fire = build_fire(Duration::from_millis(50)).await;
+	let addr = fire.local_addr().unwrap();
+	let server = tokio::task::spawn(
+		fire.ignite_with_shutdown(sleep(Duration::from_millis(100))),
+	);
+
+	let req = tokio::task::spawn(async move {
+		let addr = addr.to_string();
+		let req = hyper::Request::builder()
+			.uri(format!("http://{addr}/slow/5000"))
+			.header("host", &addr)
+			.body(fire::Body::new().into_http_body())
+			.unwrap();
+		util::send_request(req).await
+	});
+
+	let report = server.await.unwrap().unwrap();
+	assert_eq!(report.drained, 0);
+	assert_eq!(report.force_closed, 1);
+
+	assert!(req.await.unwrap().is_err());
+}
@@ -0,0 +1,169 @@
+use fire_http as fire;
+
+use fire::tls::TlsConfig;
+use fire::{get, Body, Request};
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+#[get("/")]
+fn scheme(req: &mut Request) -> String {
+	let uri = req.header().uri();
+	format!("{} {}", uri.scheme_str().unwrap(), uri.host().unwrap())
+}
+
+/// Returns a self signed certificate for localhost.
+fn self_signed() -> (TlsConfig, CertificateDer<'static>) {
+	let cert =
+		rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+
+	let tls =
+		TlsConfig::from_pem(cert.cert.pem(), cert.key_pair.serialize_pem())
+			.unwrap();
+
+	(tls, cert.cert.der().clone())
+}
+
+/// Spawns a server with a self signed certificate for localhost.
+async fn spawn_tls_server() -> (SocketAddr, CertificateDer<'static>) {
+	let (tls, cert) = self_signed();
+
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.tls(tls);
+	builder.add_route(scheme);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	tokio::task::spawn(fire.ignite());
+
+	(addr, cert)
+}
+
+/// Connects with a client which trusts the certificate.
+async fn connect(
+	addr: SocketAddr,
+	cert: CertificateDer<'static>,
+	alpn: &[u8],
+) -> TlsStream<TcpStream> {
+	let mut roots = RootCertStore::empty();
+	roots.add(cert).unwrap();
+	let mut config =
+		ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+			.with_safe_default_protocol_versions()
+			.unwrap()
+			.with_root_certificates(roots)
+			.with_no_client_auth();
+	config.alpn_protocols = vec![alpn.to_vec()];
+
+	let stream = TcpStream::connect(addr).await.unwrap();
+	let stream = TlsConnector::from(Arc::new(config))
+		.connect(ServerName::try_from("localhost").unwrap(), stream)
+		.await
+		.unwrap();
+
+	assert_eq!(stream.get_ref().1.alpn_protocol(), Some(alpn));
+
+	stream
+}
+
+/// Sends a request over http/1.1 and returns the body.
+#[cfg(feature = "http1")]
+async fn request<I>(io: I) -> String
+where
+	I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+	let (mut sender, conn) =
+		hyper::client::conn::http1::handshake(TokioIo::new(io))
+			.await
+			.unwrap();
+	tokio::task::spawn(conn);
+
+	let req = hyper::Request::builder()
+		.uri("/")
+		.header("host", "localhost")
+		.body(Body::new().into_http_body())
+		.unwrap();
+
+	let resp = sender.send_request(req).await.unwrap();
+	assert_eq!(resp.status(), 200);
+
+	Body::from_hyper(resp.into_body())
+		.into_string()
+		.await
+		.unwrap()
+}
+
+#[cfg(feature = "http1")]
+#[tokio::test]
+async fn https_request() {
+	let (addr, cert) = spawn_tls_server().await;
+	let stream = connect(addr, cert, b"http/1.1").await;
+
+	assert_eq!(request(stream).await, "https localhost");
+}
+
+#[cfg(feature = "http1")]
+#[tokio::test]
+async fn tls_per_listener() {
+	use fire::listener::Listener;
+
+	let (tls, cert) = self_signed();
+
+	let secure = Listener::bind("127.0.0.1:0").await.unwrap().with_tls(tls);
+	let secure_addr = secure.local_addr().unwrap();
+
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_listener(secure.with_name("secure"));
+	builder.add_route(scheme);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	tokio::task::spawn(fire.ignite());
+
+	let stream = TcpStream::connect(addr).await.unwrap();
+	assert_eq!(request(stream).await, "http localhost");
+
+	let stream = connect(secure_addr, cert, b"http/1.1").await;
+	assert_eq!(request(stream).await, "https localhost");
+}
+
+/// http2 clients send the host as `:authority` without a host header.
+#[cfg(feature = "http2")]
+#[tokio::test]
+async fn http2_request() {
+	use hyper_util::rt::TokioExecutor;
+
+	let (addr, cert) = spawn_tls_server().await;
+	let stream = connect(addr, cert, b"h2").await;
+
+	let (mut sender, conn) = hyper::client::conn::http2::handshake(
+		TokioExecutor::new(),
+		TokioIo::new(stream),
+	)
+	.await
+	.unwrap();
+	tokio::task::spawn(conn);
+
+	let req = hyper::Request::builder()
+		.uri("https://localhost/")
+		.body(Box::pin(Body::new().into_http_body()))
+		.unwrap();
+	assert!(req.headers().get("host").is_none());
+
+	let resp = sender.send_request(req).await.unwrap();
+	assert_eq!(resp.status(), 200);
+
+	let body = Body::from_hyper(resp.into_body())
+		.into_string()
+		.await
+		.unwrap();
+	assert_eq!(body, "https localhost");
+}
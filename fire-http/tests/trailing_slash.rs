@@ -0,0 +1,114 @@
+use fire_http as fire;
+
+use fire::get;
+use fire::routes::TrailingSlash;
+
+#[macro_use]
+mod util;
+
+#[get("/users")]
+fn users() -> &'static str {
+	"users"
+}
+
+#[get("/posts/")]
+fn posts() -> &'static str {
+	"posts"
+}
+
+#[tokio::test]
+async fn strict() {
+	let addr = spawn_server!(|builder| {
+		builder.add_route(users);
+		builder.add_route(posts);
+	});
+
+	make_request!("GET", addr, "/users/")
+		.await
+		.assert_status(404);
+	make_request!("GET", addr, "/posts")
+		.await
+		.assert_status(404);
+}
+
+#[tokio::test]
+async fn redirect() {
+	let addr = spawn_server!(|builder| {
+		builder.trailing_slash(TrailingSlash::MovedPermanently);
+		builder.add_route(users);
+		builder.add_route(posts);
+	});
+
+	make_request!("GET", addr, "/users/?page=2")
+		.await
+		.assert_status(301)
+		.assert_header("location", "/users?page=2");
+
+	make_request!("GET", addr, "/posts")
+		.await
+		.assert_status(301)
+		.assert_header("location", "/posts/");
+
+	make_request!("GET", addr, "/users")
+		.await
+		.assert_status(200)
+		.assert_body_str("users")
+		.await;
+
+	make_request!("GET", addr, "/other/")
+		.await
+		.assert_status(404);
+}
+
+#[get("/{*rest}")]
+fn catch_all() -> &'static str {
+	"rest"
+}
+
+#[tokio::test]
+async fn no_open_redirect() {
+	let addr = spawn_server!(|builder| {
+		builder.trailing_slash(TrailingSlash::MovedPermanently);
+		builder.add_route(catch_all);
+	});
+
+	// a redirect to //evil.com would lead to another host
+	make_request!("GET", addr, "//evil.com/")
+		.await
+		.assert_status(200)
+		.assert_not_header("location");
+}
+
+#[tokio::test]
+async fn permanent_redirect() {
+	let addr = spawn_server!(|builder| {
+		builder.trailing_slash(TrailingSlash::PermanentRedirect);
+		builder.add_route(users);
+	});
+
+	make_request!("POST", addr, "/users/")
+		.await
+		.assert_status(308)
+		.assert_header("location", "/users");
+}
+
+#[tokio::test]
+async fn transparent() {
+	let addr = spawn_server!(|builder| {
+		builder.trailing_slash(TrailingSlash::Transparent);
+		builder.add_route(users);
+		builder.add_route(posts);
+	});
+
+	make_request!("GET", addr, "/users/")
+		.await
+		.assert_status(200)
+		.assert_body_str("users")
+		.await;
+
+	make_request!("GET", addr, "/posts")
+		.await
+		.assert_status(200)
+		.assert_body_str("posts")
+		.await;
+}
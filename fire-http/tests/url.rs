@@ -0,0 +1,105 @@
+use fire_http as fire;
+
+use fire::extractor::{PathParam, PathStr};
+use fire::get;
+use fire::routes::ParamsNames;
+
+#[macro_use]
+mod util;
+
+#[get("/users/{id}/files/{*path}", name = "user_file")]
+fn user_file(id: &PathStr, path: &PathStr) -> String {
+	format!("{id}: {path}")
+}
+
+#[get("/codes/{code:[0-9]{3}}/{{raw}}/{*rest}")]
+fn code_rest(code: &PathStr, rest: &PathStr) -> String {
+	format!("{code} {rest}")
+}
+
+#[get("/posts/{id:u64}/{slug:[a-z-]+}")]
+fn post(id: PathParam<u64>, slug: &PathStr) -> String {
+	format!("{id} {slug}")
+}
+
+#[get("/")]
+fn index() -> &'static str {
+	"index"
+}
+
+#[tokio::test]
+async fn route_url() {
+	let url = user_file::url(42, "docs/a b.txt").query("download", true);
+	assert_eq!(url.path(), "/users/42/files/docs/a%20b.txt");
+	assert_eq!(
+		url.to_string(),
+		"/users/42/files/docs/a%20b.txt?download=true"
+	);
+	assert_eq!(index::url().to_string(), "/");
+
+	// typed constraints take their type
+	let id: u64 = 42;
+	assert_eq!(post::url(id, "hello").path(), "/posts/42/hello");
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(user_file);
+		builder.add_route(index);
+	});
+
+	make_request!("GET", addr, user_file::url("a/b", "c"))
+		.await
+		.assert_status(200)
+		.assert_body_str("a%2Fb: c")
+		.await;
+}
+
+#[tokio::test]
+async fn route_names() {
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_route(index);
+	builder.scope("/api", |api| api.add_route(user_file));
+	let fire = builder.build().await.unwrap();
+
+	let pit = fire.pit();
+	let route = pit
+		.routes()
+		.iter()
+		.find(|r| r.name.as_deref() == Some("user_file"))
+		.unwrap();
+	assert_eq!(route.path, "/api/users/{id}/files/{*path}");
+	assert_eq!(pit.routes()[0].name, None);
+}
+
+#[tokio::test]
+#[should_panic(expected = "has the same name as GET /users/{id}/files/{*path}")]
+async fn duplicate_names() {
+	#[get("/other", name = "user_file")]
+	fn other() -> &'static str {
+		"other"
+	}
+
+	let mut builder = fire::build("127.0.0.1:0").await.unwrap();
+	builder.add_route(user_file);
+	builder.add_route(other);
+}
+
+#[tokio::test]
+async fn template_names() {
+	// the macro and the router parse the template the same way
+	let template = "/codes/{code:[0-9]{3}}/{{raw}}/{*rest}";
+	let names: Vec<_> = ParamsNames::parse(template).iter().collect();
+	assert_eq!(names, ["code", "rest"]);
+
+	let url = code_rest::url(404, "a/b");
+	assert_eq!(url.path(), "/codes/404/{raw}/a/b");
+
+	let addr = spawn_server!(|builder| {
+		builder.add_route(code_rest);
+	});
+
+	make_request!("GET", addr, url)
+		.await
+		.assert_status(200)
+		.assert_body_str("404 a/b")
+		.await;
+}
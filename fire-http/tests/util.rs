@@ -31,9 +31,13 @@ macro_rules! other_err {
 pub async fn send_request(
 	req: hyper::Request<BodyHttp>,
 ) -> io::Result<hyper::Response<hyper::body::Incoming>> {
-	let client =
-		hyper_util::client::legacy::Client::builder(TokioExecutor::new())
-			.build_http();
+	#[cfg_attr(feature = "http1", allow(unused_mut))]
+	let mut builder =
+		hyper_util::client::legacy::Client::builder(TokioExecutor::new());
+	// without http1 the server only speaks http2
+	#[cfg(not(feature = "http1"))]
+	builder.http2_only(true);
+	let client = builder.build_http();
 
 	client
 		.request(req.map(Box::pin))
@@ -118,6 +122,10 @@ impl TestResponse {
 		self.inner.headers().get(key).and_then(|v| v.to_str().ok())
 	}
 
+	pub fn into_body(self) -> Body {
+		self.inner.into_body()
+	}
+
 	pub async fn assert_body_str(mut self, value: &str) -> Self {
 		let body = self
 			.inner
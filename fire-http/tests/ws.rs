@@ -1,13 +1,25 @@
-use fire::extractor::PathParam;
+use fire::connections::Overflow;
+use fire::extractor::{Ext, PathParam};
+use fire::header::StatusCode;
+use fire::resources::Resources;
+use fire::routes::Middleware;
+use fire::util::PinnedFuture;
 use fire::ws::{CloseCode, Error, WebSocket};
-use fire::Body;
 use fire::{impl_res_extractor, ws};
+use fire::{Body, Request, Response};
+
 use fire_http as fire;
+use std::future::pending;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
 
 use tokio_tungstenite::tungstenite::protocol::Role;
 use tokio_tungstenite::WebSocketStream;
 
 use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::sleep;
 use tracing_test::traced_test;
 
 #[macro_use]
@@ -176,3 +188,221 @@ async fn ws_params() {
 	// close the connection properly
 	tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 }
+
+#[tokio::test]
+async fn size_limit() {
+	#[ws("/", size_limit = 16, timeout = 5)]
+	async fn echo(mut ws: WebSocket) -> Result<(), Error> {
+		while let Some(msg) = ws.receive().await? {
+			ws.send(msg).await?;
+		}
+
+		Ok(())
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_raw_route(echo);
+	});
+
+	ws_client!(addr, "/", |ws| {
+		ws.send("short").await.expect("could not send");
+		let msg = ws.receive().await.expect("could not receive");
+		assert_eq!(msg.unwrap().to_text().unwrap(), "short");
+
+		// the server closes the connection
+		ws.send("a".repeat(64)).await.expect("could not send");
+		assert!(!matches!(ws.receive().await, Ok(Some(_))));
+	});
+}
+
+struct User(String);
+
+/// Rejects requests without a user in the query.
+struct Auth;
+
+impl Middleware for Auth {
+	fn before<'a>(
+		&'a self,
+		req: &'a mut Request,
+		_data: &'a Resources,
+	) -> PinnedFuture<'a, fire::Result<Option<Response>>> {
+		PinnedFuture::new(async move {
+			let user = req.header().uri().query().and_then(|q| {
+				q.strip_prefix("user=").map(|user| User(user.to_string()))
+			});
+			let Some(user) = user else {
+				return Ok(Some(StatusCode::UNAUTHORIZED.into()));
+			};
+
+			req.header.extensions.insert(user);
+			Ok(None)
+		})
+	}
+}
+
+#[tokio::test]
+async fn middleware() {
+	#[ws("/me")]
+	async fn me(mut ws: WebSocket, user: Ext<'_, User>) -> Result<(), Error> {
+		ws.send(user.0.clone()).await
+	}
+
+	let addr = spawn_server!(|builder| {
+		builder.add_middleware(Auth);
+		builder.add_raw_route(me);
+	});
+
+	make_request!("GET", addr, "/me", |builder| {
+		builder
+			.header("upgrade", "websocket")
+			.header("sec-websocket-version", "13")
+			.header("sec-websocket-key", "123")
+			.body(Body::new().into_http_body())
+			.unwrap()
+	})
+	.await
+	.assert_status(401);
+
+	ws_client!(addr, "/me?user=alice", |ws| {
+		let msg = ws.receive().await.expect("could not receive");
+		assert_eq!(msg.unwrap().to_text().unwrap(), "alice");
+	});
+}
+
+#[ws("/echo")]
+async fn echo(mut ws: WebSocket) -> Result<(), Error> {
+	while let Some(msg) = ws.receive().await? {
+		ws.send(msg).await?;
+	}
+
+	Ok(())
+}
+
+async fn build_echo(timeout: Duration) -> fire::Fire {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_raw_route(echo);
+	builder.shutdown_timeout(timeout);
+	builder.build().await.unwrap()
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_websockets() {
+	let fire = build_echo(Duration::from_secs(5)).await;
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.shutdown_handle();
+	let server = tokio::task::spawn(fire.ignite_with_shutdown(pending()));
+
+	ws_client!(addr, "/echo", |ws| {
+		handle.shutdown();
+		sleep(Duration::from_millis(50)).await;
+		assert!(!server.is_finished());
+
+		// the websocket keeps working until it gets closed
+		ws.send("hey").await.expect("could not send");
+		let msg = ws.receive().await.expect("could not receive");
+		assert_eq!(msg.unwrap().to_text().unwrap(), "hey");
+		ws.close(CloseCode::Normal, "".into()).await;
+
+		let report = server.await.unwrap().unwrap();
+		assert_eq!(report.drained, 1);
+		assert_eq!(report.force_closed, 0);
+	});
+}
+
+#[tokio::test]
+async fn shutdown_closes_websockets() {
+	let fire = build_echo(Duration::from_millis(50)).await;
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.shutdown_handle();
+	let server = tokio::task::spawn(fire.ignite_with_shutdown(pending()));
+
+	ws_client!(addr, "/echo", |ws| {
+		handle.shutdown();
+
+		let report = server.await.unwrap().unwrap();
+		assert_eq!(report.drained, 0);
+		assert_eq!(report.force_closed, 1);
+
+		assert!(!matches!(ws.receive().await, Ok(Some(_))));
+	});
+}
+
+#[tokio::test]
+async fn shutdown_signal() {
+	#[ws("/polite")]
+	async fn polite(mut ws: WebSocket) -> Result<(), Error> {
+		let mut signal = ws.shutdown_signal();
+
+		loop {
+			tokio::select! {
+				msg = ws.receive() => match msg? {
+					Some(msg) => ws.send(msg).await?,
+					None => return Ok(()),
+				},
+				_ = signal.wait() => break,
+			}
+		}
+
+		ws.close(CloseCode::Away, "shutdown".into()).await;
+		Ok(())
+	}
+
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_raw_route(polite);
+	builder.shutdown_timeout(Duration::from_secs(5));
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	let handle = fire.shutdown_handle();
+	let server = tokio::task::spawn(fire.ignite_with_shutdown(pending()));
+
+	ws_client!(addr, "/polite", |ws| {
+		ws.send("hey").await.expect("could not send");
+		ws.receive().await.expect("could not receive");
+
+		handle.shutdown();
+		assert!(matches!(ws.receive().await, Ok(None)));
+
+		// the handler closed the websocket before the timeout
+		let report = tokio::time::timeout(Duration::from_secs(1), server)
+			.await
+			.expect("shutdown waited for the timeout")
+			.unwrap()
+			.unwrap();
+		assert_eq!(report.drained, 1);
+		assert_eq!(report.force_closed, 0);
+	});
+}
+
+#[tokio::test]
+async fn websockets_count_towards_limit() {
+	let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+	let mut builder = fire::build(socket_addr).await.unwrap();
+	builder.add_raw_route(echo);
+	builder.max_connections(1);
+	builder.connection_overflow(Overflow::Reject);
+	let fire = builder.build().await.unwrap();
+	let addr = fire.local_addr().unwrap();
+	let pit = fire.pit();
+	let stats = pit.clone();
+	tokio::task::spawn(fire.ignite());
+
+	ws_client!(addr, "/echo", |ws| {
+		ws.send("hey").await.expect("could not send");
+		ws.receive().await.expect("could not receive");
+		assert_eq!(stats.connections().current, 1);
+
+		// the server closes the second connection
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await;
+		let mut buf = vec![];
+		assert!(matches!(stream.read_to_end(&mut buf).await, Ok(0) | Err(_)));
+		assert_eq!(stats.connections().rejected, 1);
+
+		ws.close(CloseCode::Normal, "".into()).await;
+	});
+
+	sleep(Duration::from_millis(50)).await;
+	assert_eq!(pit.connections().current, 0);
+}